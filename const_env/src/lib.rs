@@ -1,22 +1,61 @@
 #![cfg_attr(
     feature = "tracked",
-    feature(proc_macro_tracked_env)
+    feature(proc_macro_tracked_env, track_path)
 )]
 
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
 
-/// Configure a `const` or `static` item from an environment variable.
+/// Configure a `const` or `static` item, or the associated constants of an `impl` or `trait`, from environment
+/// variables.
 /// 
 /// # Usage
 /// This macro takes one optional string literal parameter, which if present is used as the name of the environment
 /// variable that the literal value will be parsed from. If no parameter is present, then the name of the Rust
 /// static/const item decorated by this attribute will be used as the environment variable name.
 /// 
+/// The name may be followed by comma separated options:
+/// - `variants = [A, B, ...]` restricts an enum variant default such as `Backend::A`, or the names
+///   in a flag set default such as `Flags::A.union(Flags::B)`, to the listed names.
+/// - `choices = [path, ...]` makes the environment variable select one of the listed paths, named
+///   either in full or by the last segment. Any other value is rejected.
+/// - `arg` or `arg = N` keeps a function call default such as `AtomicU32::new(100)` and replaces
+///   only its first argument, or the argument at zero-based index `N`.
+/// - `template = "..."` substitutes the value as a literal into the `{0}` placeholder of a const
+///   expression, and `split = "..."` first splits the value into several values for `{0}`, `{1}`, ...
+/// - `fields` lets each field of a struct default be overridden by a `{NAME}_{FIELD}` variable.
+/// - `elements` lets each element of an array default be overridden by a `{NAME}_{INDEX}` variable.
+/// - `nested` is like `fields` and `elements` but uses `{NAME}__{FIELD}` variables and recurses into
+///   nested struct and array defaults, e.g. `APP__DB__PORT`.
+/// - `prefix = "..."` prepends a prefix to the item name when it is used as the environment variable
+///   name.
+/// - `required` makes it an error for the environment variable to be missing.
+/// - `flags` treats a single flag default such as `Flags::A` as a flag set, so `A|B` becomes
+///   `Flags::A.union(Flags::B)` and an empty value `Flags::empty()`. `union` and `empty()` defaults are always
+///   flag sets.
+/// - `dotenv` also reads variables from a `.env` file next to the crate's `Cargo.toml`, if there is
+///   one, and `dotenv = "path"` from a file at a path relative to it, which must exist. Variables of
+///   the real environment take precedence. Setting the `CONST_ENV_DOTENV` environment variable to a
+///   path does the same for every macro of this crate.
+/// - `file = "path"` reads the value from a TOML file, at a path relative to the crate's `Cargo.toml`, if the
+///   environment variable is not set. `key = "table.key"` gives the dotted key of the value, which otherwise is
+///   the lowercase environment variable name. Tables become struct expressions and arrays become arrays, in the
+///   shape of the default.
+/// - `format = "json"` parses the environment variable contents as JSON. Objects become struct expressions,
+///   arrays become arrays and `null` becomes `None`, in the shape of the default.
+/// - `sources = [...]` reads the value from the first of the listed sources that defines it: `env` for
+///   environment variables, `dotenv` or `dotenv("path")` for a dotenv file, `toml("path")` or
+///   `toml("path", "table")` for a TOML file, and `default` for the item's own value. Without `default`, a value
+///   must be found. Errors say which source a value came from. This replaces the `dotenv` and `file` options.
+/// 
 /// The static or const item being decorated should be assigned a value, which will function as the default value if
 /// no such matching environment variable is defined.
 /// 
+/// The macro may also decorate an `impl` or `trait` block, which configures every associated constant that has a
+/// value. Their environment variable names default to the type and constant name, e.g. `SERVER_MAX_CONNECTIONS`, and
+/// an `env_item` attribute on an associated constant can give it an explicit name or options.
+/// 
 /// # Examples
 /// 
 /// ```rust
@@ -45,6 +84,121 @@ pub fn env_item(attr: TokenStream, item: TokenStream) -> TokenStream {
     const_env_impl::env_item(attr.into(), item.into(), read_env).into()
 }
 
+/// Select the type of a type alias from an environment variable.
+/// 
+/// # Usage
+/// This macro takes one optional string literal parameter, which if present is used as the name of the environment
+/// variable that the type will be parsed from. If no parameter is present, then the upper snake case name of the
+/// type alias will be used as the environment variable name, e.g. `FLOAT` for `Float`.
+/// 
+/// The name may be followed by a `choices = [path, ...]` option, which restricts the environment variable to
+/// selecting one of the listed types. Without it, any type is accepted. Options that convert values, such as
+/// `template` or `fields`, are rejected.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// // In this example, the environment variable FLOAT will be used to select the type of the Float
+/// // alias. If no FLOAT environment variable is defined, then the alias will be for f32.
+/// #[const_env::env_type(choices = [f32, f64])]
+/// type Float = f32;
+/// ```
+#[proc_macro_attribute]
+pub fn env_type(attr: TokenStream, item: TokenStream) -> TokenStream {
+    #[cfg(not(feature = "tracked"))]
+    let read_env = StableEnv {};
+    #[cfg(feature = "tracked")]
+    let read_env = TrackedEnv {};
+    const_env_impl::env_type(attr.into(), item.into(), read_env).into()
+}
+
+/// Insert `true` if an environment variable is defined, and `false` otherwise.
+/// 
+/// # Usage
+/// This macro takes a single string literal, the name of the environment variable. Because it expands to a
+/// boolean literal, it can be used in `const` contexts, and `if` conditions using it are removed by the optimizer.
+/// 
+/// Unlike `option_env!("NAME").is_some()`, this reads the environment the same way as the other macros of this
+/// crate, including tracking the environment variable when the `tracked` feature is enabled.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// // In this example, VERBOSE is true if the VERBOSE_BUILD environment variable is defined,
+/// // with any value.
+/// const VERBOSE: bool = const_env::env_defined!("VERBOSE_BUILD");
+/// ```
+#[proc_macro]
+pub fn env_defined(tokens: TokenStream) -> TokenStream {
+    #[cfg(not(feature = "tracked"))]
+    let read_env = StableEnv {};
+    #[cfg(feature = "tracked")]
+    let read_env = TrackedEnv {};
+    const_env_impl::env_defined(tokens.into(), read_env).into()
+}
+
+/// Select one of several expressions depending on the value of an environment variable.
+/// 
+/// # Usage
+/// This macro takes a string literal naming the environment variable, followed by a block of arms similar to
+/// the arms of a `match` expression. Each arm is either one or more string literals separated by `|`, or `_`,
+/// followed by `=>` and an expression. The first arm matching the value of the environment variable is selected
+/// and only its expression is compiled. The `_` arm matches any value, as well as an unset environment variable.
+/// 
+/// It is a compile error if no arm matches and there is no `_` arm, or if any arm follows the `_` arm.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// # struct PgDriver; struct SqliteDriver; struct NullDriver;
+/// # impl PgDriver { fn new() -> Self { PgDriver } }
+/// # impl SqliteDriver { fn new() -> Self { SqliteDriver } }
+/// // In this example, the driver is chosen by the BACKEND environment variable. Only the selected
+/// // expression is compiled, so the other drivers don't even need to exist in this build.
+/// let driver = const_env::env_match!("BACKEND", {
+///     "pg" | "postgres" => PgDriver::new(),
+///     "sqlite" => SqliteDriver::new(),
+///     _ => NullDriver
+/// });
+/// ```
+#[proc_macro]
+pub fn env_match(tokens: TokenStream) -> TokenStream {
+    #[cfg(not(feature = "tracked"))]
+    let read_env = StableEnv {};
+    #[cfg(feature = "tracked")]
+    let read_env = TrackedEnv {};
+    const_env_impl::env_match(tokens.into(), read_env).into()
+}
+
+/// Keep or remove any item depending on environment variables.
+/// 
+/// # Usage
+/// This macro takes a predicate similar to the one of the `cfg` attribute. The item is kept if the predicate is true
+/// and removed otherwise. Predicates are:
+/// - `"NAME" = "value"`, true if the environment variable `NAME` is defined with the value `value`.
+/// - `defined("NAME")`, true if the environment variable `NAME` is defined.
+/// - `not(predicate)`, `all(predicate, ...)` and `any(predicate, ...)`, which combine other predicates.
+/// 
+/// Unlike custom `cfg` flags, this does not require changing `RUSTFLAGS`, which invalidates the build cache of all
+/// dependencies.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// // In this example, the function is only compiled if the TELEMETRY environment variable is "on"
+/// // and the DEBUG_HOOKS environment variable is not defined.
+/// #[const_env::env_cfg(all("TELEMETRY" = "on", not(defined("DEBUG_HOOKS"))))]
+/// fn report() {}
+/// ```
+#[proc_macro_attribute]
+pub fn env_cfg(attr: TokenStream, item: TokenStream) -> TokenStream {
+    #[cfg(not(feature = "tracked"))]
+    let read_env = StableEnv {};
+    #[cfg(feature = "tracked")]
+    let read_env = TrackedEnv {};
+    const_env_impl::env_cfg(attr.into(), item.into(), read_env).into()
+}
+
 /// Deprecated alias of env_item, use env_item instead. Will be deleted upon the next major
 /// version bump.
 #[deprecated = "Deprecated alias of env_item, use env_item instead"]
@@ -62,6 +216,16 @@ pub fn from_env(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// - The second argument is any expression, which will be used as the default value of this macro
 ///   if there is no environment variable defined.
 /// 
+/// The type of the literal is inferred from the default value, for example a string literal default means that
+/// the environment variable contents are quoted as a string. When the default value doesn't show the type, such
+/// as a path to another constant, the type can be given explicitly either as `"NAME" as TYPE` or as an extra
+/// first argument, `TYPE, "NAME"`. The conversion then follows that type.
+/// 
+/// The conversion is the same as in the [env_item] macro, and the same options can follow the default value,
+/// for example `env_lit!("BACKEND", Backend::Postgres, variants = [Postgres, Sqlite])`. The additional `raw`
+/// option inserts the environment variable contents as raw tokens instead, quoting only top-level string,
+/// byte string, character and byte literals, which was the behavior of earlier versions of this macro.
+/// 
 /// Unlike in the [env_item] macro, there is no relationship between the environment variable name passed
 /// to this macro and the name of the static or const item the macro value is assigned to. They can be the
 /// same or different. Only the string name passed to this macro is used to look up an environment variable.
@@ -73,6 +237,11 @@ pub fn from_env(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// // Rust constant. If no such HELLO_WORLD variable is defined, then the value 0 will be used as the default
 /// // value of the constant.
 /// const FOO: u8 = const_env::env_lit!("HELLO_WORLD", 0);
+/// 
+/// // Here the default is another constant, so the type is stated to quote the value as a string.
+/// const DEFAULT_NAME: &str = "world";
+/// const NAME: &str = const_env::env_lit!("NAME" as &str, DEFAULT_NAME);
+/// const PORT: u16 = const_env::env_lit!(u16, "PORT", 8080);
 /// ```
 #[proc_macro]
 pub fn env_lit(tokens: TokenStream) -> TokenStream {
@@ -83,20 +252,116 @@ pub fn env_lit(tokens: TokenStream) -> TokenStream {
     const_env_impl::env_lit(tokens.into(), read_env).into()
 }
 
+/// Derive an associated `FROM_ENV` constant whose fields are configured from environment variables.
+/// 
+/// # Usage
+/// Every field of the struct must have an `#[env(...)]` attribute with a `default = ...` option,
+/// which is used as the field's value if no matching environment variable is defined. The attribute
+/// otherwise takes the same arguments as [macro@env_item]: an optional string literal environment
+/// variable name, defaulting to the upper case field name, and the same options.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// // In this example, the PORT and HOST environment variables will be used to define the fields of
+/// // `Config::FROM_ENV`. If they are not defined, then the fields will have the values 8080 and
+/// // "localhost".
+/// #[derive(const_env::EnvConfig)]
+/// struct Config {
+///     #[env("PORT", default = 8080)]
+///     port: u16,
+///     #[env(default = "localhost")]
+///     host: &'static str,
+/// }
+/// 
+/// const CONFIG: Config = Config::FROM_ENV;
+/// ```
+#[proc_macro_derive(EnvConfig, attributes(env))]
+pub fn env_config(item: TokenStream) -> TokenStream {
+    #[cfg(not(feature = "tracked"))]
+    let read_env = StableEnv {};
+    #[cfg(feature = "tracked")]
+    let read_env = TrackedEnv {};
+    const_env_impl::env_config(item.into(), read_env).into()
+}
+
+/// Declare several constants configured from environment variables at once.
+/// 
+/// # Usage
+/// The block contains constant declarations without the `const` keyword, each of which behaves as if
+/// it were annotated with [macro@env_item]. An entry may have an `#[env(...)]` attribute taking the same
+/// arguments as [macro@env_item].
+/// 
+/// Options shared by all entries are given in a `#![env(...)]` inner attribute at the start of the
+/// block. Useful shared options are:
+/// - `prefix = "..."` prepends a prefix to every environment variable name derived from a constant name.
+/// - `required` makes it an error for any environment variable to be missing.
+/// - `listing = NAME` renames the generated listing constant.
+/// 
+/// In addition to the constants, the macro generates a `&[(&str, &str, &str)]` listing of the name,
+/// environment variable name and value of every entry. It is named after the shared prefix, e.g.
+/// `APP_CONFIG_ENTRIES` for `prefix = "APP_"`, or `CONFIG_ENTRIES` without a prefix. Several blocks in
+/// the same module need different prefixes or `listing` names.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// // In this example, the APP_PORT and APP_HOSTNAME environment variables will be used to define
+/// // the PORT and HOST constants. If they are not defined, then the constants will have the values
+/// // 8080 and "localhost".
+/// const_env::config! {
+///     #![env(prefix = "APP_")]
+///     pub PORT: u16 = 8080;
+///     #[env("APP_HOSTNAME")]
+///     pub HOST: &str = "localhost";
+/// }
+/// 
+/// assert_eq!(("PORT", "APP_PORT", "8080"), APP_CONFIG_ENTRIES[0]);
+/// ```
+#[proc_macro]
+pub fn config(tokens: TokenStream) -> TokenStream {
+    #[cfg(not(feature = "tracked"))]
+    let read_env = StableEnv {};
+    #[cfg(feature = "tracked")]
+    let read_env = TrackedEnv {};
+    const_env_impl::config(tokens.into(), read_env).into()
+}
+
 #[cfg(feature = "tracked")]
 struct TrackedEnv;
 
 #[cfg(feature = "tracked")]
 impl const_env_impl::ReadEnv for TrackedEnv {
-    fn read_env(&self, var_name: &String) -> Option<String> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
         proc_macro::tracked_env::var(var_name).ok()
     }
+
+    fn read_file(&self, path: &std::path::Path) -> std::io::Result<String> {
+        if let Some(path) = path.to_str() {
+            proc_macro::tracked_path::path(path);
+        }
+        std::fs::read_to_string(path)
+    }
+
+    // Variables aren't listed, since listing isn't tracked and setting a new element variable
+    // wouldn't rebuild the crate. Every element variable is read through `read_env` instead.
 }
 
 struct StableEnv;
 
 impl const_env_impl::ReadEnv for StableEnv {
-    fn read_env(&self, var_name: &String) -> Option<String> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
         std::env::var(var_name).ok()
     }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        Some(env_var_names(prefix))
+    }
+}
+
+fn env_var_names(prefix: &str) -> Vec<String> {
+    std::env::vars_os()
+        .filter_map(|(name, _)| name.into_string().ok())
+        .filter(|name| name.starts_with(prefix))
+        .collect()
 }
\ No newline at end of file
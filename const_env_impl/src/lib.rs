@@ -1,34 +1,122 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use proc_macro2::TokenStream;
-use quote::{ToTokens, quote_spanned};
+use quote::{ToTokens, quote, quote_spanned};
 use syn::{Expr, ExprLit, Lit};
 use syn::spanned::Spanned;
 
 pub trait ReadEnv {
-    fn read_env(&self, var_name: &String) -> Option<String>;
+    fn read_env(&self, var_name: &str) -> Option<String>;
+
+    /// Reads a build setting such as `CARGO_MANIFEST_DIR`. Environments that rename or compute
+    /// variables read settings from the environment they wrap as they are.
+    fn read_setting(&self, name: &str) -> Option<String> {
+        self.read_env(name)
+    }
+
+    /// Reads a configuration file such as a `.env` file.
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    /// Lists the names of the defined variables that start with a prefix, such as the element
+    /// variables of an array. Returns `None` if the variables can't be listed, in which case every
+    /// possible name is read instead, and only the element right after the end of an array is
+    /// checked for being out of bounds.
+    fn var_names(&self, _prefix: &str) -> Option<Vec<String>> {
+        None
+    }
+}
+
+impl<T: ReadEnv + ?Sized> ReadEnv for &T {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        (**self).read_env(var_name)
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        (**self).read_setting(name)
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        (**self).read_file(path)
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        (**self).var_names(prefix)
+    }
+}
+
+impl<T: ReadEnv + ?Sized> ReadEnv for Box<T> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        (**self).read_env(var_name)
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        (**self).read_setting(name)
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        (**self).read_file(path)
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        (**self).var_names(prefix)
+    }
+}
+
+/// Fixed variables, for example parsed from a configuration file. There are no files.
+impl ReadEnv for HashMap<String, String> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.get(var_name).cloned()
+    }
+
+    fn read_file(&self, _path: &Path) -> std::io::Result<String> {
+        Err(std::io::ErrorKind::NotFound.into())
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        Some(names_with_prefix(self.keys(), prefix))
+    }
+}
+
+fn names_with_prefix<'a>(names: impl Iterator<Item = &'a String>, prefix: &str) -> Vec<String> {
+    names.filter(|name| name.starts_with(prefix)).cloned().collect()
 }
 
 pub struct TestEnv {
-    env_vars: HashMap<String, String>
+    env_vars: HashMap<String, String>,
+    files: HashMap<PathBuf, String>
 }
 
 impl TestEnv {
     pub fn builder() -> TestEnvBuilder {
         TestEnvBuilder {
-            env_vars: HashMap::new()
+            env_vars: HashMap::new(),
+            files: HashMap::new()
         }
     }
 }
 
 impl ReadEnv for TestEnv {
-    fn read_env(&self, var_name: &String) -> Option<String> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
         self.env_vars.get(var_name).cloned()
     }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        self.files.get(path)
+            .cloned()
+            .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        self.env_vars.var_names(prefix)
+    }
 }
 
 pub struct TestEnvBuilder {
-    env_vars: HashMap<String, String>
+    env_vars: HashMap<String, String>,
+    files: HashMap<PathBuf, String>
 }
 
 impl TestEnvBuilder {
@@ -37,48 +125,603 @@ impl TestEnvBuilder {
         self
     }
 
+    pub fn file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+
     pub fn build(self) -> TestEnv {
         TestEnv {
-            env_vars: self.env_vars
+            env_vars: self.env_vars,
+            files: self.files
+        }
+    }
+}
+
+/// Reads variables from the first environment, or otherwise from the second. Files are read from
+/// the first environment, or from the second if the first doesn't have them.
+pub struct Chain<A: ReadEnv, B: ReadEnv>(pub A, pub B);
+
+impl<A: ReadEnv, B: ReadEnv> ReadEnv for Chain<A, B> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.0.read_env(var_name).or_else(|| self.1.read_env(var_name))
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        self.0.read_setting(name).or_else(|| self.1.read_setting(name))
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        match self.0.read_file(path) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => self.1.read_file(path),
+            result => result
+        }
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        let mut names = self.0.var_names(prefix)?;
+        names.extend(self.1.var_names(prefix)?);
+        Some(names)
+    }
+}
+
+/// Reads every variable with a prefix, e.g. `MYAPP_PORT` for `PORT`.
+pub struct Prefixed<E: ReadEnv> {
+    prefix: String,
+    read_env: E
+}
+
+impl<E: ReadEnv> Prefixed<E> {
+    pub fn new(prefix: impl Into<String>, read_env: E) -> Self {
+        Prefixed {
+            prefix: prefix.into(),
+            read_env
+        }
+    }
+}
+
+impl<E: ReadEnv> ReadEnv for Prefixed<E> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.read_env.read_env(&format!("{}{}", self.prefix, var_name))
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        self.read_env.read_setting(name)
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        self.read_env.read_file(path)
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        let names = self.read_env.var_names(&format!("{}{}", self.prefix, prefix))?;
+        Some(names.iter().map(|name| name[self.prefix.len()..].to_string()).collect())
+    }
+}
+
+/// Reads every variable by the name returned by a function, e.g. to rename or lowercase variables.
+pub struct Mapped<E: ReadEnv, F: Fn(&str) -> String> {
+    read_env: E,
+    map: F
+}
+
+impl<E: ReadEnv, F: Fn(&str) -> String> Mapped<E, F> {
+    pub fn new(read_env: E, map: F) -> Self {
+        Mapped {
+            read_env,
+            map
+        }
+    }
+}
+
+impl<E: ReadEnv, F: Fn(&str) -> String> ReadEnv for Mapped<E, F> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.read_env.read_env(&(self.map)(var_name))
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        self.read_env.read_setting(name)
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        self.read_env.read_file(path)
+    }
+}
+
+/// Reads variables from an environment, and computes the value of variables it doesn't define with
+/// a function.
+pub struct Fallback<E: ReadEnv, F: Fn(&str) -> Option<String>> {
+    read_env: E,
+    fallback: F
+}
+
+impl<E: ReadEnv, F: Fn(&str) -> Option<String>> Fallback<E, F> {
+    pub fn new(read_env: E, fallback: F) -> Self {
+        Fallback {
+            read_env,
+            fallback
+        }
+    }
+}
+
+impl<E: ReadEnv, F: Fn(&str) -> Option<String>> ReadEnv for Fallback<E, F> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.read_env.read_env(var_name).or_else(|| (self.fallback)(var_name))
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        self.read_env.read_setting(name)
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        self.read_env.read_file(path)
+    }
+}
+
+/// Reads fixed variables on top of an environment, so they take precedence over its variables.
+pub struct Overlay<E: ReadEnv>(pub HashMap<String, String>, pub E);
+
+impl<E: ReadEnv> ReadEnv for Overlay<E> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.0.get(var_name).cloned().or_else(|| self.1.read_env(var_name))
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        self.1.read_setting(name)
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        self.1.read_file(path)
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        let mut names = self.1.var_names(prefix)?;
+        names.extend(self.0.var_names(prefix)?);
+        Some(names)
+    }
+}
+
+/// The `dotenv` option: `dotenv` reads an optional `.env` file, while `dotenv = "path"` reads a file
+/// which must exist.
+#[derive(Clone)]
+enum DotenvOption {
+    Default(syn::Ident),
+    Path(syn::LitStr)
+}
+
+/// The variables of a dotenv file.
+type DotenvVars = HashMap<String, String>;
+
+/// Adds the variables of the dotenv file given by the `dotenv` option, or otherwise by the
+/// crate-wide `CONST_ENV_DOTENV` variable, to the environment.
+fn with_dotenv<'a, E: ReadEnv>(option: Option<&DotenvOption>, read_env: &'a E) -> Result<Chain<&'a E, DotenvVars>, syn::Error> {
+    let vars = load_dotenv(option, read_env)?
+        .map(|(_, vars)| vars)
+        .unwrap_or_default();
+    Ok(Chain(read_env, vars))
+}
+
+/// Loads the dotenv file given by the `dotenv` option, or otherwise by the crate-wide
+/// `CONST_ENV_DOTENV` variable. Paths are relative to the directory of the crate being built.
+/// Returns `None` if there is no such file.
+fn load_dotenv(option: Option<&DotenvOption>, read_env: &impl ReadEnv) -> Result<Option<(PathBuf, DotenvVars)>, syn::Error> {
+    let (path, required, span) = match option {
+        Some(DotenvOption::Default(ident)) => (".env".to_string(), false, ident.span()),
+        Some(DotenvOption::Path(path)) => (path.value(), true, path.span()),
+        None => match read_env.read_setting("CONST_ENV_DOTENV") {
+            Some(path) => (path, true, proc_macro2::Span::call_site()),
+            None => return Ok(None)
+        }
+    };
+    let path = manifest_relative_path(&path, read_env);
+    let vars = match read_env.read_file(&path) {
+        Ok(contents) => parse_dotenv(&contents)
+            .map_err(|err| syn::Error::new(span, format!("Failed to parse dotenv file {}: {}", path.display(), err)))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && !required => return Ok(None),
+        Err(err) => return Err(syn::Error::new(span, format!("Failed to read dotenv file {}: {}", path.display(), err)))
+    };
+    Ok(Some((path, vars)))
+}
+
+/// Values of a TOML configuration file rendered as JSON. A variable is looked up by its lowercase
+/// name, e.g. `max_connections` for `MAX_CONNECTIONS`, within an optional table, unless an explicit
+/// key is given for it.
+struct TomlEnv {
+    table: toml::Table,
+    table_key: Option<String>,
+    explicit_key: Option<(String, String)>
+}
+
+impl TomlEnv {
+    fn load(file: &syn::LitStr, table_key: Option<&syn::LitStr>, read_env: &impl ReadEnv) -> Result<(PathBuf, TomlEnv), syn::Error> {
+        let path = manifest_relative_path(&file.value(), read_env);
+        let contents = read_env.read_file(&path)
+            .map_err(|err| syn::Error::new_spanned(file, format!("Failed to read configuration file {}: {}", path.display(), err)))?;
+        let table = contents.parse()
+            .map_err(|err| syn::Error::new_spanned(file, format!("Failed to parse configuration file {}: {}", path.display(), err)))?;
+        Ok((path, TomlEnv { table, table_key: table_key.map(syn::LitStr::value), explicit_key: None }))
+    }
+
+    /// The table that variables are looked up in.
+    fn variables(&self) -> Option<&toml::Table> {
+        let Some(table_key) = &self.table_key else {
+            return Some(&self.table);
+        };
+        let mut table = &self.table;
+        for part in table_key.split('.') {
+            table = table.get(part)?.as_table()?;
+        }
+        Some(table)
+    }
+
+    fn key(&self, var_name: &str) -> String {
+        let key = match &self.explicit_key {
+            Some((name, key)) if name == var_name => key.clone(),
+            _ => var_name.to_lowercase()
+        };
+        match &self.table_key {
+            Some(table_key) => format!("{}.{}", table_key, key),
+            None => key
+        }
+    }
+}
+
+impl ReadEnv for TomlEnv {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        let key = self.key(var_name);
+        let mut parts = key.split('.');
+        let mut value = self.table.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(toml_to_json(value))
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        let Some(variables) = self.variables() else {
+            return Some(Vec::new());
+        };
+        let names = variables.keys().map(|key| key.to_uppercase()).collect::<Vec<_>>();
+        Some(names_with_prefix(names.iter(), prefix))
+    }
+}
+
+fn toml_to_json(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => json_string(value),
+        toml::Value::Integer(value) => value.to_string(),
+        // Debug formatting keeps the decimal point of whole numbers such as `1.0`.
+        toml::Value::Float(value) => format!("{:?}", value),
+        toml::Value::Boolean(value) => value.to_string(),
+        toml::Value::Datetime(value) => json_string(&value.to_string()),
+        toml::Value::Array(values) => format!("[{}]", values.iter().map(toml_to_json).collect::<Vec<_>>().join(", ")),
+        toml::Value::Table(table) => format!("{{{}}}", table.iter()
+            .map(|(key, value)| format!("{}: {}", json_string(key), toml_to_json(value)))
+            .collect::<Vec<_>>()
+            .join(", "))
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// An entry of the `sources` option.
+#[derive(Clone)]
+enum SourceSpec {
+    /// `env`, the environment variables.
+    Env,
+    /// `dotenv` or `dotenv("path")`.
+    Dotenv(DotenvOption),
+    /// `toml("path")` or `toml("path", "table")`.
+    Toml(syn::LitStr, Option<syn::LitStr>),
+    /// `default`, the item's own value. Later sources would never be used, so it must be last.
+    Default(syn::Ident)
+}
+
+impl syn::parse::Parse for SourceSpec {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let ident: syn::Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "env" => Ok(SourceSpec::Env),
+            "default" => Ok(SourceSpec::Default(ident)),
+            "dotenv" if input.peek(syn::token::Paren) => {
+                let content;
+                syn::parenthesized!(content in input);
+                Ok(SourceSpec::Dotenv(DotenvOption::Path(content.parse()?)))
+            },
+            "dotenv" => Ok(SourceSpec::Dotenv(DotenvOption::Default(ident))),
+            "toml" => {
+                let content;
+                syn::parenthesized!(content in input);
+                let file = content.parse()?;
+                let table_key = if content.is_empty() {
+                    None
+                } else {
+                    content.parse::<syn::Token![,]>()?;
+                    Some(content.parse()?)
+                };
+                Ok(SourceSpec::Toml(file, table_key))
+            },
+            _ => Err(syn::Error::new_spanned(&ident, format!("Unknown source `{}`, expected `env`, `dotenv`, `toml` or `default`", ident)))
+        }
+    }
+}
+
+/// A source that values are read from.
+struct Source<'a> {
+    read_env: Box<dyn ReadEnv + 'a>,
+    format: Option<ValueFormat>,
+    /// Where values of this source come from for diagnostics, or `None` for the environment.
+    origin: Option<String>,
+    /// Whether values are looked up by the `key` option.
+    keyed: bool
+}
+
+/// Reads values from several sources in order of precedence, so the first source that defines a
+/// variable is used.
+struct Layered<'a, E: ReadEnv> {
+    read_env: &'a E,
+    sources: Vec<Source<'a>>
+}
+
+impl<E: ReadEnv> ReadEnv for Layered<'_, E> {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.sources.iter().find_map(|source| {
+            let value = source.read_env.read_env(var_name)?;
+            // Single JSON values are read like plain environment variable contents.
+            Some(match source.format {
+                Some(ValueFormat::Json) => match parse_json(&value) {
+                    Ok(StructuredValue::Scalar(scalar)) => scalar,
+                    _ => value
+                },
+                None => value
+            })
+        })
+    }
+
+    fn read_setting(&self, name: &str) -> Option<String> {
+        self.read_env.read_setting(name)
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        self.read_env.read_file(path)
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        let mut names = Vec::new();
+        for source in &self.sources {
+            names.extend(source.read_env.var_names(prefix)?);
+        }
+        Some(names)
+    }
+}
+
+impl<E: ReadEnv> Layered<'_, E> {
+    /// Where the value of a variable comes from, or `None` if it is the environment or not defined.
+    fn origin_of(&self, var_name: &str) -> Option<&str> {
+        self.sources.iter()
+            .find(|source| source.read_env.read_env(var_name).is_some())
+            .and_then(|source| source.origin.as_deref())
+    }
+}
+
+/// Adds where a value came from to an error converting it, if that wasn't the environment.
+fn with_origin(err: syn::Error, var_name: &str, origin: Option<&str>) -> syn::Error {
+    match origin {
+        Some(origin) => syn::Error::new(err.span(), format!("{} (value of {} from {})", err, var_name, origin)),
+        None => err
+    }
+}
+
+/// The sources of an item's value: those of the `sources` option, or otherwise the environment
+/// followed by the files of the `dotenv` and `file` options.
+fn item_sources<'a, E: ReadEnv>(args: &ItemArgs, var_name: &str, read_env: &'a E) -> Result<Layered<'a, E>, syn::Error> {
+    let specs = match &args.sources {
+        Some(specs) => specs.clone(),
+        None => {
+            let mut specs = vec![SourceSpec::Env];
+            specs.extend(args.dotenv.clone().map(SourceSpec::Dotenv));
+            specs.extend(args.file.clone().map(|file| SourceSpec::Toml(file, None)));
+            specs
+        }
+    };
+    let mut sources = Vec::new();
+    for spec in specs {
+        match spec {
+            SourceSpec::Env => {
+                sources.push(Source { read_env: Box::new(read_env), format: args.format, origin: None, keyed: false });
+                // The crate-wide dotenv file comes right after the environment, unless the sources are explicit.
+                if args.sources.is_none()
+                    && args.dotenv.is_none()
+                    && let Some((path, dotenv)) = load_dotenv(None, read_env)? {
+                    let origin = Some(format!("dotenv file {}", path.display()));
+                    sources.push(Source { read_env: Box::new(dotenv), format: args.format, origin, keyed: false });
+                }
+            },
+            SourceSpec::Dotenv(option) => {
+                if let Some((path, dotenv)) = load_dotenv(Some(&option), read_env)? {
+                    let origin = Some(format!("dotenv file {}", path.display()));
+                    sources.push(Source { read_env: Box::new(dotenv), format: args.format, origin, keyed: false });
+                }
+            },
+            SourceSpec::Toml(file, table_key) => {
+                let (path, mut toml_env) = TomlEnv::load(&file, table_key.as_ref(), read_env)?;
+                toml_env.explicit_key = args.key.as_ref().map(|key| (var_name.to_string(), key.value()));
+                let origin = Some(format!("configuration file {}", path.display()));
+                sources.push(Source { read_env: Box::new(toml_env), format: Some(ValueFormat::Json), origin, keyed: true });
+            },
+            SourceSpec::Default(_) => break
+        }
+    }
+    Ok(Layered { read_env, sources })
+}
+
+/// Resolves a path relative to the directory of the crate being built.
+fn manifest_relative_path(path: &str, read_env: &impl ReadEnv) -> PathBuf {
+    read_env.read_setting("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(path)
+}
+
+/// Parses `NAME=value` lines of a dotenv file. Lines may start with `export`, values may be single
+/// quoted, or double quoted with escapes, and `#` starts a comment. Values are used like the contents
+/// of environment variables, which are literal contents with Rust escapes, so `\"` and `\\` are kept
+/// as they are.
+fn parse_dotenv(contents: &str) -> Result<HashMap<String, String>, String> {
+    let mut vars = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line.split_once('=')
+            .ok_or_else(|| format!("line {}: expected `NAME=value`", index + 1))?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("line {}: invalid variable name `{}`", index + 1, name));
+        }
+        let value = value.trim_start();
+        let (value, rest) = if let Some(quoted) = value.strip_prefix('\'') {
+            let end = quoted.find('\'')
+                .ok_or_else(|| format!("line {}: unterminated single quoted value", index + 1))?;
+            (quoted[..end].to_string(), &quoted[end + 1..])
+        } else if let Some(quoted) = value.strip_prefix('"') {
+            let mut unescaped = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((end, '"')) => break end,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => unescaped.push('\n'),
+                        Some((_, 'r')) => unescaped.push('\r'),
+                        Some((_, 't')) => unescaped.push('\t'),
+                        Some((_, c @ ('"' | '\\'))) => {
+                            unescaped.push('\\');
+                            unescaped.push(c);
+                        },
+                        Some((_, c)) => unescaped.push(c),
+                        None => return Err(format!("line {}: unterminated double quoted value", index + 1))
+                    },
+                    Some((_, c)) => unescaped.push(c),
+                    None => return Err(format!("line {}: unterminated double quoted value", index + 1))
+                }
+            };
+            (unescaped, &quoted[end + 1..])
+        } else {
+            let end = value.find(" #").unwrap_or(value.len());
+            (value[..end].trim_end().to_string(), "")
+        };
+        let rest = rest.trim_start();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(format!("line {}: unexpected `{}` after quoted value", index + 1, rest));
         }
+        vars.insert(name.to_string(), value);
     }
+    Ok(vars)
 }
 
 struct MacroInput {
     env_var_name: syn::LitStr,
+    ty: Option<syn::Type>,
     default_value: syn::Expr,
+    args: ItemArgs,
 }
 
 impl syn::parse::Parse for MacroInput {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let args = syn::punctuated::Punctuated::<syn::Expr, syn::token::Comma>::parse_terminated(input)?;
-        if args.len() != 2 {
-            return Err(syn::Error::new(input.span(), "Exactly 2 arguments expected"));
-        }
-        let env_var_name = match args.first().unwrap() {
-            Expr::Lit(ExprLit { lit: syn::Lit::Str(lit_str), .. }) => {
-                lit_str.clone()
-            },
-            otherwise => return Err(syn::Error::new(otherwise.span(), "Expected first argument to be a string literal"))
+        // Either `"NAME", DEFAULT`, `"NAME" as TYPE, DEFAULT` or `TYPE, "NAME", DEFAULT`, optionally
+        // followed by the same options as `env_item`.
+        let (env_var_name, ty) = if input.peek(syn::LitStr) {
+            let env_var_name = input.parse()?;
+            let ty = if input.peek(syn::Token![as]) {
+                input.parse::<syn::Token![as]>()?;
+                Some(input.parse()?)
+            } else {
+                None
+            };
+            (env_var_name, ty)
+        } else {
+            let ty = input.parse()?;
+            input.parse::<syn::Token![,]>()?;
+            if !input.peek(syn::LitStr) {
+                return Err(syn::Error::new(input.span(), "Expected environment variable name after the type to be a string literal"));
+            }
+            (input.parse()?, Some(ty))
         };
-        let default_value = args.last().unwrap().clone();
+        input.parse::<syn::Token![,]>()?;
+        let default_value = input.parse()?;
+        if input.peek(syn::Token![,]) {
+            input.parse::<syn::Token![,]>()?;
+        }
+        if !input.is_empty() && !input.peek(syn::Ident) {
+            return Err(syn::Error::new(input.span(), "Expected options after the default value"));
+        }
+        let args: ItemArgs = input.parse()?;
+        if let Some(default) = &args.default {
+            return Err(syn::Error::new_spanned(default, "The `default` option is not valid in `env_lit!`, pass the default value as an argument instead"));
+        }
+        if let Some(listing) = &args.listing {
+            return Err(syn::Error::new_spanned(listing, "The `listing` option is only valid in `config!` blocks"));
+        }
+        if let Some(prefix) = &args.prefix {
+            return Err(syn::Error::new_spanned(prefix, "The `prefix` option is not valid in `env_lit!`, the environment variable name is always explicit"));
+        }
+        if let Some(raw) = &args.raw
+            && (ty.is_some() || args.has_conversion_options() || args.file.is_some()) {
+            return Err(syn::Error::new_spanned(raw, "The `raw` option cannot be combined with an explicit type, conversion options or a configuration file"));
+        }
         Ok(Self {
             env_var_name,
-            default_value
+            ty,
+            default_value,
+            args
         })
     }
 }
 
 /// Include environment variable contents as a Rust literal.
 pub fn env_lit(tokens: TokenStream, read_env: impl ReadEnv) -> TokenStream {
-    let input: MacroInput = match syn::parse2(tokens) {
-        Ok(input) => input,
-        Err(err) => return err.to_compile_error()
-    };
-    let env_var_value = match read_env.read_env(&input.env_var_name.value()) {
-        Some(env_var_value) => env_var_value,
-        None => return input.default_value.into_token_stream()
-    };
+    match try_env_lit(tokens, read_env) {
+        Ok(tokens) => tokens,
+        Err(err) => err.into_compile_error()
+    }
+}
+
+fn try_env_lit(tokens: TokenStream, read_env: impl ReadEnv) -> Result<TokenStream, syn::Error> {
+    let input: MacroInput = syn::parse2(tokens)?;
+    let var_name = input.env_var_name.value();
+    if input.args.raw.is_some() {
+        return match with_dotenv(input.args.dotenv.as_ref(), &read_env)?.read_env(&var_name) {
+            Some(env_var_value) => Ok(raw_env_lit(&input, env_var_value)),
+            None if input.args.required => {
+                Err(syn::Error::new_spanned(&input.env_var_name, format!("Environment variable {} is required but not set", var_name)))
+            },
+            None => Ok(input.default_value.into_token_stream())
+        };
+    }
+    // Without an explicit type the conversion only goes by the shape of the default.
+    Ok(match configure_expr(&input.args, &var_name, &input.default_value, input.ty.as_ref(), &read_env)? {
+        Some(new_expr) => new_expr.into_token_stream(),
+        None => input.default_value.into_token_stream()
+    })
+}
+
+/// The original `env_lit!` conversion, kept for the `raw` option: the contents are inserted as raw
+/// tokens, except for top-level string-like literals which are quoted.
+fn raw_env_lit(input: &MacroInput, env_var_value: String) -> TokenStream {
     let env_var_value_tokens = match env_var_value.parse::<TokenStream>() {
         Ok(tokens) => tokens,
         Err(err) => return syn::Error::new(input.env_var_name.span(), format!("{}", err)).to_compile_error()
@@ -131,70 +774,1273 @@ pub fn env_item(attr: TokenStream, item: TokenStream, read_env: impl ReadEnv) ->
 }
 
 fn try_env_item(attr: TokenStream, item: TokenStream, read_env: impl ReadEnv) -> Result<TokenStream, syn::Error> {
+    let attr_span = attr.span();
+    let args: ItemArgs = syn::parse2(attr)?;
+    args.check_item_options()?;
     if let Ok(mut item_const) = syn::parse2::<syn::ItemConst>(item.clone()) {
-        let default_var_name = format!("{}", item_const.ident);
-        let var_name = extract_var_name(attr, default_var_name)?;
-        let var_value = match read_env.read_env(&var_name) {
-            Some(val) => val,
+        let var_name = args.var_name(item_const.ident.to_string());
+        let new_expr = match configure_expr(&args, &var_name, &item_const.expr, Some(&item_const.ty), &read_env)? {
+            Some(new_expr) => new_expr,
             None => return Ok(item)
         };
-        let new_expr = value_to_literal(&var_value, &item_const.expr)?;
         let span = item_const.span();
-        item_const.expr = Box::new(new_expr);
+        *item_const.expr = new_expr;
         Ok(quote_spanned!(span => #item_const))
     } else if let Ok(mut item_static) = syn::parse2::<syn::ItemStatic>(item.clone()) {
-        let default_var_name = format!("{}", item_static.ident);
-        let var_name = extract_var_name(attr, default_var_name)?;
-        let var_value = match read_env.read_env(&var_name) {
-            Some(val) => val,
+        let var_name = args.var_name(item_static.ident.to_string());
+        let new_expr = match configure_expr(&args, &var_name, &item_static.expr, Some(&item_static.ty), &read_env)? {
+            Some(new_expr) => new_expr,
             None => return Ok(item)
         };
-        let new_expr = value_to_literal(&var_value, &item_static.expr)?;
         let span = item_static.span();
-        item_static.expr = Box::new(new_expr);
+        *item_static.expr = new_expr;
         Ok(quote_spanned!(span => #item_static))
+    } else if let Ok(mut item_impl) = syn::parse2::<syn::ItemImpl>(item.clone()) {
+        check_block_args(&args, attr_span)?;
+        let type_name = match &*item_impl.self_ty {
+            syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last().map(|last| last.ident.to_string()),
+            _ => None
+        }.ok_or_else(|| syn::Error::new_spanned(&item_impl.self_ty, "Unable to derive environment variable names from this type"))?;
+        for impl_item in item_impl.items.iter_mut() {
+            if let syn::ImplItem::Const(impl_const) = impl_item {
+                configure_associated_const(&args, &type_name, &mut impl_const.attrs, &impl_const.ident, &impl_const.ty, Some(&mut impl_const.expr), &read_env)?;
+            }
+        }
+        Ok(item_impl.into_token_stream())
+    } else if let Ok(mut item_trait) = syn::parse2::<syn::ItemTrait>(item.clone()) {
+        check_block_args(&args, attr_span)?;
+        let type_name = item_trait.ident.to_string();
+        for trait_item in item_trait.items.iter_mut() {
+            if let syn::TraitItem::Const(trait_const) = trait_item {
+                let default = trait_const.default.as_mut().map(|(_, default)| default);
+                configure_associated_const(&args, &type_name, &mut trait_const.attrs, &trait_const.ident, &trait_const.ty, default, &read_env)?;
+            }
+        }
+        Ok(item_trait.into_token_stream())
+    } else if let Ok(mut item_type) = syn::parse2::<syn::ItemType>(item.clone()) {
+        args.check_type_alias_options(attr_span)?;
+        let var_name = args.var_name(screaming_snake_case(&item_type.ident.to_string()));
+        let var_value = match read_value(&args, &var_name, &item_sources(&args, &var_name, &read_env)?, &item_type.ty)? {
+            Some((StructuredValue::Scalar(var_value), _)) => var_value,
+            Some(_) => return Err(syn::Error::new_spanned(&item_type.ty, "A type alias can only be configured by a single value")),
+            None if args.required => {
+                return Err(syn::Error::new_spanned(&item_type.ty, format!("Environment variable {} is required but not set", var_name)));
+            },
+            None => return Ok(item)
+        };
+        *item_type.ty = match &args.choices {
+            Some(choices) => syn::Type::Path(syn::TypePath {
+                qself: None,
+                path: select_choice(var_value.trim(), choices)?.clone()
+            }),
+            None => syn::parse_str(&var_value)
+                .map_err(|_| syn::Error::new_spanned(&item_type.ty, "Failed to parse environment variable contents as valid type"))?
+        };
+        Ok(item_type.into_token_stream())
     } else {
-        Err(syn::Error::new(attr.span(), "Macro is only valid on const or static items"))
+        Err(syn::Error::new(attr_span, "Macro is only valid on const, static, type, impl or trait items"))
     }
 }
 
-fn extract_var_name(attr: TokenStream, default: String) -> Result<String, syn::Error> {
-    if attr.is_empty() {
-        return Ok(default);
+/// Inner implementation details of `const_env::env_type`.
+pub fn env_type(attr: TokenStream, item: TokenStream, read_env: impl ReadEnv) -> TokenStream {
+    if syn::parse2::<syn::ItemType>(item.clone()).is_err() {
+        return syn::Error::new(attr.span(), "Macro is only valid on type aliases, use env_item for values").into_compile_error();
     }
-    let span = attr.span();
-    let expr: Expr = syn::parse2(attr)
-        .map_err(|_| syn::Error::new(span,"Unable to parse attribute args as expression"))?;
-    extract_var_name_from_expr(&expr)
+    env_item(attr, item, read_env)
 }
 
-fn extract_var_name_from_expr(expr: &Expr) -> Result<String, syn::Error> {
-    match expr {
-        Expr::Lit(literal) => {
-            match &literal.lit {
-                Lit::Str(lit_str) => {
-                    Ok(lit_str.value())
-                },
-                _ => Err(syn::Error::new_spanned(expr, "Attribute arguments are not a valid string literal"))
-            }
-        },
-        Expr::Paren(paren) => {
-            extract_var_name_from_expr(&paren.expr)
+fn check_block_args(args: &ItemArgs, attr_span: proc_macro2::Span) -> Result<(), syn::Error> {
+    match &args.var_name {
+        Some(_) => Err(syn::Error::new(attr_span, "An environment variable name cannot be given for a whole impl or trait, put `#[env_item(\"NAME\")]` on the associated constant instead")),
+        None => Ok(())
+    }
+}
+
+/// Configures an associated constant of an `impl` or `trait` annotated with `env_item`, using
+/// options from an `env_item` attribute on the constant itself if there is one. The environment
+/// variable name defaults to the type and constant name, e.g. `SERVER_MAX_CONNECTIONS`.
+fn configure_associated_const(block_args: &ItemArgs, type_name: &str, attrs: &mut Vec<syn::Attribute>, ident: &syn::Ident, ty: &syn::Type, expr: Option<&mut Expr>, read_env: &impl ReadEnv) -> Result<(), syn::Error> {
+    let const_attr = attrs.iter()
+        .position(|attr| attr.path().segments.last().is_some_and(|last| last.ident == "env_item"))
+        .map(|index| attrs.remove(index));
+    let const_args: ItemArgs = match &const_attr {
+        Some(syn::Attribute { meta: syn::Meta::List(list), .. }) => syn::parse2(list.tokens.clone())?,
+        Some(syn::Attribute { meta: syn::Meta::NameValue(name_value), .. }) => {
+            return Err(syn::Error::new_spanned(name_value, "Expected `#[env_item]` or `#[env_item(...)]`"));
         },
-        _ => {
-            Err(syn::Error::new_spanned(expr, "Attribute arguments are not a valid string literal expression"))
+        _ => ItemArgs::default()
+    };
+    const_args.check_item_options()?;
+    let Some(expr) = expr else {
+        return match const_attr {
+            Some(const_attr) => Err(syn::Error::new_spanned(const_attr, "Associated constant requires a default value to be configured from the environment")),
+            None => Ok(())
+        };
+    };
+    let args = const_args.with_shared(block_args);
+    let var_name = args.var_name(format!("{}_{}", screaming_snake_case(type_name), syn::ext::IdentExt::unraw(ident)));
+    if let Some(new_expr) = configure_expr(&args, &var_name, expr, Some(ty), read_env)? {
+        *expr = new_expr;
+    }
+    Ok(())
+}
+
+/// Converts a type name such as `HttpServer` into `HTTP_SERVER`.
+fn screaming_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        if index > 0 && c.is_uppercase() {
+            let previous = chars[index - 1];
+            let next_is_lowercase = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase() || previous.is_ascii_digit() || (previous.is_uppercase() && next_is_lowercase) {
+                result.push('_');
+            }
         }
+        result.extend(c.to_uppercase());
     }
+    result
 }
 
-fn value_to_literal(value: &str, original_expr: &Expr) -> Result<Expr, syn::Error> {
-    Ok(match original_expr {
-        Expr::Array(array) => {
-            syn::Expr::Array(syn::parse_str::<syn::ExprArray>(value)
-                .map_err(|_| syn::Error::new_spanned(array, "Failed to parse environment variable contents as valid array"))?)
-        },
-        Expr::Unary(unary) => {
-            // A unary sign indicates this is a numeric literal which doesn't need any
+/// Inner implementation details of `const_env::env_cfg`.
+pub fn env_cfg(attr: TokenStream, item: TokenStream, read_env: impl ReadEnv) -> TokenStream {
+    let predicate: EnvPredicate = match syn::parse2(attr) {
+        Ok(predicate) => predicate,
+        Err(err) => return err.into_compile_error()
+    };
+    let read_env = match with_dotenv(None, &read_env) {
+        Ok(read_env) => read_env,
+        Err(err) => return err.into_compile_error()
+    };
+    if predicate.evaluate(&read_env) {
+        item
+    } else {
+        TokenStream::new()
+    }
+}
+
+/// A `cfg`-like predicate over environment variables, as used by `env_cfg`.
+enum EnvPredicate {
+    /// `"NAME" = "value"`
+    Equals(syn::LitStr, syn::LitStr),
+    /// `defined("NAME")`
+    Defined(syn::LitStr),
+    Not(Box<EnvPredicate>),
+    All(Vec<EnvPredicate>),
+    Any(Vec<EnvPredicate>)
+}
+
+impl EnvPredicate {
+    fn evaluate(&self, read_env: &impl ReadEnv) -> bool {
+        match self {
+            EnvPredicate::Equals(name, value) => read_env.read_env(&name.value()).is_some_and(|actual| actual == value.value()),
+            EnvPredicate::Defined(name) => read_env.read_env(&name.value()).is_some(),
+            EnvPredicate::Not(predicate) => !predicate.evaluate(read_env),
+            EnvPredicate::All(predicates) => predicates.iter().all(|predicate| predicate.evaluate(read_env)),
+            EnvPredicate::Any(predicates) => predicates.iter().any(|predicate| predicate.evaluate(read_env))
+        }
+    }
+}
+
+impl syn::parse::Parse for EnvPredicate {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::LitStr) {
+            let name = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            return Ok(EnvPredicate::Equals(name, input.parse()?));
+        }
+        let ident: syn::Ident = input.parse()?;
+        let content;
+        syn::parenthesized!(content in input);
+        match ident.to_string().as_str() {
+            "defined" => Ok(EnvPredicate::Defined(content.parse()?)),
+            "not" => Ok(EnvPredicate::Not(Box::new(content.parse()?))),
+            "all" | "any" => {
+                let predicates = syn::punctuated::Punctuated::<EnvPredicate, syn::Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect();
+                Ok(if ident == "all" { EnvPredicate::All(predicates) } else { EnvPredicate::Any(predicates) })
+            },
+            _ => Err(syn::Error::new_spanned(&ident, format!("Unknown predicate `{}`, expected `\"NAME\" = \"value\"`, `defined`, `not`, `all` or `any`", ident)))
+        }
+    }
+}
+
+/// Inner implementation details of `const_env::env_defined`.
+pub fn env_defined(tokens: TokenStream, read_env: impl ReadEnv) -> TokenStream {
+    let env_var_name: syn::LitStr = match syn::parse2(tokens) {
+        Ok(env_var_name) => env_var_name,
+        Err(err) => return err.to_compile_error()
+    };
+    let read_env = match with_dotenv(None, &read_env) {
+        Ok(read_env) => read_env,
+        Err(err) => return err.to_compile_error()
+    };
+    let defined = read_env.read_env(&env_var_name.value()).is_some();
+    syn::LitBool::new(defined, env_var_name.span()).into_token_stream()
+}
+
+/// Inner implementation details of `const_env::env_match`.
+pub fn env_match(tokens: TokenStream, read_env: impl ReadEnv) -> TokenStream {
+    let input: MatchInput = match syn::parse2(tokens) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error()
+    };
+    let name = input.env_var_name.value();
+    let value = match with_dotenv(None, &read_env) {
+        Ok(read_env) => read_env.read_env(&name),
+        Err(err) => return err.to_compile_error()
+    };
+    let selected = input.arms.iter().find(|arm| match &arm.patterns {
+        Some(patterns) => value.as_ref().is_some_and(|value| patterns.iter().any(|pattern| pattern.value() == *value)),
+        None => true
+    });
+    if let Some(arm) = selected {
+        return arm.body.to_token_stream();
+    }
+    let patterns = input.arms.iter()
+        .flat_map(|arm| arm.patterns.iter().flatten())
+        .map(|pattern| pattern.value())
+        .collect::<Vec<_>>();
+    let message = match value {
+        Some(value) => {
+            let mut message = format!("Environment variable {} has value \"{}\" which matches no arm and there is no `_` arm, expected one of: {}",
+                name, value, patterns.join(", "));
+            if let Some(suggestion) = did_you_mean(&value, patterns.iter().cloned()) {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+            message
+        },
+        None => format!("Environment variable {} is not set and there is no `_` arm", name)
+    };
+    syn::Error::new(input.env_var_name.span(), message).to_compile_error()
+}
+
+struct MatchInput {
+    env_var_name: syn::LitStr,
+    arms: Vec<MatchArm>,
+}
+
+/// A `"a" | "b" => expr` arm of `env_match`, where `patterns` is `None` for the `_` arm.
+struct MatchArm {
+    patterns: Option<Vec<syn::LitStr>>,
+    body: Expr,
+}
+
+impl syn::parse::Parse for MatchInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let env_var_name = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let content;
+        syn::braced!(content in input);
+        let mut arms: Vec<MatchArm> = Vec::new();
+        while !content.is_empty() {
+            // Unlike in `match`, arms after `_` are an error rather than a warning.
+            let follows_wildcard = arms.iter().any(|arm| arm.patterns.is_none());
+            let patterns = if content.peek(syn::Token![_]) {
+                let wildcard = content.parse::<syn::Token![_]>()?;
+                if follows_wildcard {
+                    return Err(syn::Error::new_spanned(wildcard, "Duplicate `_` arm"));
+                }
+                None
+            } else {
+                let patterns = syn::punctuated::Punctuated::<syn::LitStr, syn::Token![|]>::parse_separated_nonempty(&content)?;
+                if follows_wildcard {
+                    return Err(syn::Error::new_spanned(&patterns, "Unreachable arm, the `_` arm before it matches every value"));
+                }
+                Some(patterns.into_iter().collect())
+            };
+            content.parse::<syn::Token![=>]>()?;
+            let body: Expr = content.parse()?;
+            // Like in `match`, block arms don't need a trailing comma.
+            let comma_required = !content.is_empty() && !matches!(body, Expr::Block(_));
+            if comma_required || content.peek(syn::Token![,]) {
+                content.parse::<syn::Token![,]>()?;
+            }
+            arms.push(MatchArm { patterns, body });
+        }
+        if !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(Self {
+            env_var_name,
+            arms
+        })
+    }
+}
+
+/// Inner implementation details of `const_env::EnvConfig`.
+pub fn env_config(item: TokenStream, read_env: impl ReadEnv) -> TokenStream {
+    match try_env_config(item, read_env) {
+        Ok(tokens) => tokens,
+        Err(err) => err.into_compile_error()
+    }
+}
+
+fn try_env_config(item: TokenStream, read_env: impl ReadEnv) -> Result<TokenStream, syn::Error> {
+    let input: syn::DeriveInput = syn::parse2(item)?;
+    let syn::Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "EnvConfig can only be derived for structs"));
+    };
+    let mut field_values = Vec::new();
+    for (index, field) in data.fields.iter().enumerate() {
+        let attr = field.attrs.iter()
+            .find(|attr| attr.path().is_ident("env"))
+            .ok_or_else(|| syn::Error::new_spanned(field, "Every field of an EnvConfig struct requires an `#[env(default = ...)]` attribute"))?;
+        let args: ItemArgs = match &attr.meta {
+            syn::Meta::List(list) => syn::parse2(list.tokens.clone())?,
+            meta => return Err(syn::Error::new_spanned(meta, "Expected `#[env(default = ...)]`"))
+        };
+        if let Some(listing) = &args.listing {
+            return Err(syn::Error::new_spanned(listing, "The `listing` option is only valid in `config!` blocks"));
+        }
+        args.check_not_raw()?;
+        let default = args.default.as_ref()
+            .ok_or_else(|| syn::Error::new_spanned(attr, "Missing `default = ...` option"))?;
+        let var_name = match (&args.var_name, &field.ident) {
+            (None, Some(ident)) => args.var_name(syn::ext::IdentExt::unraw(ident).to_string().to_uppercase()),
+            (None, None) => return Err(syn::Error::new_spanned(attr, "Tuple struct fields require an explicit environment variable name")),
+            (Some(var_name), _) => var_name.clone()
+        };
+        let value = configure_expr(&args, &var_name, default, Some(&field.ty), &read_env)?
+            .unwrap_or_else(|| default.clone());
+        field_values.push(match &field.ident {
+            Some(ident) => quote!(#ident: #value),
+            None => {
+                let index = syn::Index::from(index);
+                quote!(#index: #value)
+            }
+        });
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub const FROM_ENV: Self = Self { #(#field_values),* };
+        }
+    })
+}
+
+/// Inner implementation details of `const_env::config`.
+pub fn config(tokens: TokenStream, read_env: impl ReadEnv) -> TokenStream {
+    match try_config(tokens, read_env) {
+        Ok(tokens) => tokens,
+        Err(err) => err.into_compile_error()
+    }
+}
+
+fn try_config(tokens: TokenStream, read_env: impl ReadEnv) -> Result<TokenStream, syn::Error> {
+    let input: ConfigInput = syn::parse2(tokens)?;
+    let mut items = Vec::new();
+    let mut listing = Vec::new();
+    for entry in &input.entries {
+        let args = entry.args.with_shared(&input.shared_args);
+        let var_name = args.var_name(entry.ident.to_string());
+        let value = configure_expr(&args, &var_name, &entry.default_value, Some(&entry.ty), &read_env)?
+            .unwrap_or_else(|| entry.default_value.clone());
+        let ConfigEntry { attrs, vis, ident, ty, .. } = entry;
+        items.push(quote!(#(#attrs)* #vis const #ident: #ty = #value;));
+        let name = ident.to_string();
+        let value = value.to_token_stream().to_string();
+        listing.push(quote!((#name, #var_name, #value)));
+    }
+    let listing_ident = &input.listing_ident;
+    Ok(quote! {
+        #(#items)*
+        /// The name, environment variable name and value of every constant in this `config!` block.
+        #[allow(dead_code)]
+        pub(crate) const #listing_ident: &[(&str, &str, &str)] = &[#(#listing),*];
+    })
+}
+
+/// Input of the `config!` macro: optional shared options in a `#![env(...)]` inner attribute,
+/// followed by constant declarations without the `const` keyword.
+struct ConfigInput {
+    shared_args: ItemArgs,
+    listing_ident: syn::Ident,
+    entries: Vec<ConfigEntry>,
+}
+
+struct ConfigEntry {
+    attrs: Vec<syn::Attribute>,
+    args: ItemArgs,
+    vis: syn::Visibility,
+    ident: syn::Ident,
+    ty: syn::Type,
+    default_value: Expr,
+}
+
+impl syn::parse::Parse for ConfigInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut shared_args = ItemArgs::default();
+        for attr in input.call(syn::Attribute::parse_inner)? {
+            if !attr.path().is_ident("env") {
+                return Err(syn::Error::new_spanned(attr, "Only `#![env(...)]` inner attributes are supported"));
+            }
+            shared_args = attr.parse_args()?;
+        }
+        if let Some(var_name) = &shared_args.var_name {
+            return Err(syn::Error::new(input.span(), format!("An environment variable name such as `{}` cannot be shared by all constants", var_name)));
+        }
+        if let Some(default) = &shared_args.default {
+            return Err(syn::Error::new_spanned(default, "The `default` option cannot be shared by all constants"));
+        }
+        shared_args.check_not_raw()?;
+        let listing_ident = shared_args.listing.take()
+            .unwrap_or_else(|| default_listing_ident(shared_args.prefix.as_ref()));
+        let mut entries = Vec::new();
+        while !input.is_empty() {
+            let mut attrs = input.call(syn::Attribute::parse_outer)?;
+            let args: ItemArgs = match attrs.iter().position(|attr| attr.path().is_ident("env")) {
+                Some(index) => attrs.remove(index).parse_args()?,
+                None => ItemArgs::default()
+            };
+            if let Some(default) = &args.default {
+                return Err(syn::Error::new_spanned(default, "The `default` option is not valid in `config!` blocks, assign the default value instead"));
+            }
+            if let Some(listing) = &args.listing {
+                return Err(syn::Error::new_spanned(listing, "The `listing` option must be shared by all constants in a `#![env(...)]` attribute"));
+            }
+            args.check_not_raw()?;
+            let vis = input.parse()?;
+            let ident = input.parse()?;
+            input.parse::<syn::Token![:]>()?;
+            let ty = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            let default_value = input.parse()?;
+            input.parse::<syn::Token![;]>()?;
+            entries.push(ConfigEntry { attrs, args, vis, ident, ty, default_value });
+        }
+        Ok(Self {
+            shared_args,
+            listing_ident,
+            entries
+        })
+    }
+}
+
+/// The name of the listing of a `config!` block without the `listing` option, which is derived from
+/// the shared prefix so that blocks with different prefixes can share a module, e.g.
+/// `APP_CONFIG_ENTRIES` for `prefix = "APP_"`.
+fn default_listing_ident(prefix: Option<&syn::LitStr>) -> syn::Ident {
+    let prefix = prefix.map(|prefix| prefix.value()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>());
+    let name = match prefix.as_deref().map(|prefix| prefix.trim_matches('_')) {
+        Some(prefix) if prefix.starts_with(|c: char| c.is_ascii_alphabetic()) => format!("{}_CONFIG_ENTRIES", prefix),
+        _ => "CONFIG_ENTRIES".to_string()
+    };
+    syn::Ident::new(&name, proc_macro2::Span::call_site())
+}
+
+/// Arguments of the `env_item` attribute: an optional environment variable name followed by
+/// `key = value` options.
+#[derive(Clone, Default)]
+struct ItemArgs {
+    var_name: Option<String>,
+    variants: Option<Vec<syn::Ident>>,
+    choices: Option<Vec<syn::Path>>,
+    arg: Option<usize>,
+    template: Option<syn::LitStr>,
+    split: Option<syn::LitStr>,
+    part_overrides: Option<PartOverrides>,
+    default: Option<Expr>,
+    prefix: Option<syn::LitStr>,
+    required: bool,
+    flags: bool,
+    listing: Option<syn::Ident>,
+    raw: Option<syn::Ident>,
+    dotenv: Option<DotenvOption>,
+    file: Option<syn::LitStr>,
+    key: Option<syn::LitStr>,
+    format: Option<ValueFormat>,
+    sources: Option<Vec<SourceSpec>>,
+}
+
+impl ItemArgs {
+    /// The environment variable name, which is the explicitly given name or otherwise the default
+    /// name with any prefix.
+    fn var_name(&self, default_name: String) -> String {
+        match (&self.var_name, &self.prefix) {
+            (Some(var_name), _) => var_name.clone(),
+            (None, Some(prefix)) => format!("{}{}", prefix.value(), default_name),
+            (None, None) => default_name
+        }
+    }
+
+    /// Rejects options that are only valid for `EnvConfig` fields, `config!` blocks or `env_lit!`.
+    fn check_item_options(&self) -> Result<(), syn::Error> {
+        if let Some(default) = &self.default {
+            return Err(syn::Error::new_spanned(default, "The `default` option is only valid on fields of an `EnvConfig` struct, use the item's own value instead"));
+        }
+        if let Some(listing) = &self.listing {
+            return Err(syn::Error::new_spanned(listing, "The `listing` option is only valid in `config!` blocks"));
+        }
+        self.check_not_raw()
+    }
+
+    /// Rejects the `raw` option, which only `env_lit!` supports.
+    fn check_not_raw(&self) -> Result<(), syn::Error> {
+        match &self.raw {
+            Some(raw) => Err(syn::Error::new_spanned(raw, "The `raw` option is only valid in `env_lit!`")),
+            None => Ok(())
+        }
+    }
+
+    /// Rejects options that convert values, which a type alias has none of. Only `choices` applies
+    /// to types.
+    fn check_type_alias_options(&self, attr_span: proc_macro2::Span) -> Result<(), syn::Error> {
+        let value_option = [
+            ("variants", self.variants.is_some()),
+            ("arg", self.arg.is_some()),
+            ("template", self.template.is_some()),
+            ("split", self.split.is_some()),
+            ("fields", matches!(self.part_overrides, Some(PartOverrides::Fields))),
+            ("elements", matches!(self.part_overrides, Some(PartOverrides::Elements))),
+            ("nested", matches!(self.part_overrides, Some(PartOverrides::Nested))),
+            ("flags", self.flags),
+            ("format", self.format.is_some())
+        ].into_iter().find_map(|(name, given)| given.then_some(name));
+        match value_option {
+            Some(name) => Err(syn::Error::new(attr_span, format!("The `{}` option is not valid on type aliases, which can only be replaced by a type or one of the `choices`", name))),
+            None => Ok(())
+        }
+    }
+
+    /// Whether any option changes how the environment variable contents are converted.
+    fn has_conversion_options(&self) -> bool {
+        self.variants.is_some()
+            || self.choices.is_some()
+            || self.arg.is_some()
+            || self.template.is_some()
+            || self.part_overrides.is_some()
+            || self.flags
+            || self.format.is_some()
+            || self.sources.is_some()
+    }
+
+    /// Fills in options that were not given with the options shared by a whole `config!` block,
+    /// `impl` or `trait`.
+    fn with_shared(&self, shared: &ItemArgs) -> ItemArgs {
+        let args = self.clone();
+        ItemArgs {
+            var_name: args.var_name,
+            variants: args.variants.or_else(|| shared.variants.clone()),
+            choices: args.choices.or_else(|| shared.choices.clone()),
+            arg: args.arg.or(shared.arg),
+            template: args.template.or_else(|| shared.template.clone()),
+            split: args.split.or_else(|| shared.split.clone()),
+            part_overrides: args.part_overrides.or(shared.part_overrides),
+            default: args.default,
+            prefix: args.prefix.or_else(|| shared.prefix.clone()),
+            required: args.required || shared.required,
+            flags: args.flags || shared.flags,
+            listing: args.listing,
+            raw: args.raw,
+            dotenv: args.dotenv.or_else(|| shared.dotenv.clone()),
+            file: args.file.or_else(|| shared.file.clone()),
+            key: args.key,
+            format: args.format.or(shared.format),
+            sources: args.sources.or_else(|| shared.sources.clone())
+        }
+    }
+}
+
+impl syn::parse::Parse for ItemArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = ItemArgs::default();
+        if !input.is_empty() && !input.peek(syn::Ident) {
+            let expr: Expr = input.parse()?;
+            args.var_name = Some(extract_var_name_from_expr(&expr)?);
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        let mut seen = Vec::new();
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            if seen.contains(&key) {
+                return Err(syn::Error::new_spanned(&key, format!("Duplicate option `{}`", key)));
+            }
+            match key.to_string().as_str() {
+                "variants" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let content;
+                    syn::bracketed!(content in input);
+                    let variants = syn::punctuated::Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated(&content)?;
+                    args.variants = Some(variants.into_iter().collect());
+                },
+                "choices" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let content;
+                    syn::bracketed!(content in input);
+                    let choices = syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated(&content)?;
+                    args.choices = Some(choices.into_iter().collect());
+                },
+                "arg" => {
+                    let index = if input.peek(syn::Token![=]) {
+                        input.parse::<syn::Token![=]>()?;
+                        input.parse::<syn::LitInt>()?.base10_parse()?
+                    } else {
+                        0
+                    };
+                    args.arg = Some(index);
+                },
+                "template" => {
+                    input.parse::<syn::Token![=]>()?;
+                    args.template = Some(input.parse()?);
+                },
+                "split" => {
+                    input.parse::<syn::Token![=]>()?;
+                    args.split = Some(input.parse()?);
+                },
+                "file" => {
+                    input.parse::<syn::Token![=]>()?;
+                    args.file = Some(input.parse()?);
+                },
+                "key" => {
+                    input.parse::<syn::Token![=]>()?;
+                    args.key = Some(input.parse()?);
+                },
+                "sources" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let content;
+                    syn::bracketed!(content in input);
+                    let sources: Vec<SourceSpec> = syn::punctuated::Punctuated::<SourceSpec, syn::Token![,]>::parse_terminated(&content)?
+                        .into_iter()
+                        .collect();
+                    if sources.is_empty() {
+                        return Err(syn::Error::new_spanned(&key, "The `sources` option requires at least one source"));
+                    }
+                    if let Some(SourceSpec::Default(default)) = sources.iter().rev().skip(1).find(|source| matches!(source, SourceSpec::Default(_))) {
+                        return Err(syn::Error::new_spanned(default, "The `default` source must be the last source"));
+                    }
+                    args.sources = Some(sources);
+                },
+                "format" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let format: syn::LitStr = input.parse()?;
+                    args.format = Some(match format.value().as_str() {
+                        "json" => ValueFormat::Json,
+                        other => return Err(syn::Error::new_spanned(&format, format!("Unknown format `{}`, expected `json`", other)))
+                    });
+                },
+                "default" => {
+                    input.parse::<syn::Token![=]>()?;
+                    args.default = Some(input.parse()?);
+                },
+                "prefix" => {
+                    input.parse::<syn::Token![=]>()?;
+                    args.prefix = Some(input.parse()?);
+                },
+                "required" => args.required = true,
+                "flags" => args.flags = true,
+                "raw" => args.raw = Some(key.clone()),
+                "dotenv" => {
+                    args.dotenv = Some(if input.peek(syn::Token![=]) {
+                        input.parse::<syn::Token![=]>()?;
+                        DotenvOption::Path(input.parse()?)
+                    } else {
+                        DotenvOption::Default(key.clone())
+                    });
+                },
+                "listing" => {
+                    input.parse::<syn::Token![=]>()?;
+                    args.listing = Some(input.parse()?);
+                },
+                "fields" | "elements" | "nested" if args.part_overrides.is_some() => {
+                    return Err(syn::Error::new_spanned(&key, "The `fields`, `elements` and `nested` options cannot be combined"));
+                },
+                "fields" => args.part_overrides = Some(PartOverrides::Fields),
+                "elements" => args.part_overrides = Some(PartOverrides::Elements),
+                "nested" => args.part_overrides = Some(PartOverrides::Nested),
+                _ => return Err(syn::Error::new_spanned(&key, format!("Unknown option `{}`", key)))
+            }
+            seen.push(key);
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        if let (Some(split), None) = (&args.split, &args.template) {
+            return Err(syn::Error::new_spanned(split, "The `split` option requires the `template` option"));
+        }
+        if let Some(sources) = &args.sources {
+            let replaced = args.dotenv.as_ref().map(|_| "dotenv").or(args.file.as_ref().map(|_| "file"));
+            if let Some(replaced) = replaced {
+                return Err(syn::Error::new(input.span(), format!("The `sources` option cannot be combined with the `{}` option, list the file as a source instead", replaced)));
+            }
+            if let Some(key) = &args.key
+                && !sources.iter().any(|source| matches!(source, SourceSpec::Toml(..))) {
+                return Err(syn::Error::new_spanned(key, "The `key` option requires a `toml` source"));
+            }
+        } else if let (Some(key), None) = (&args.key, &args.file) {
+            return Err(syn::Error::new_spanned(key, "The `key` option requires the `file` option"));
+        }
+        Ok(args)
+    }
+}
+
+/// Computes the expression that replaces the default of an item, or `None` if no environment
+/// variable affecting it is set.
+fn configure_expr(args: &ItemArgs, var_name: &str, original_expr: &Expr, ty: Option<&syn::Type>, read_env: &impl ReadEnv) -> Result<Option<Expr>, syn::Error> {
+    let read_env = &item_sources(args, var_name, read_env)?;
+    let mut new_expr = match read_value(args, var_name, read_env, original_expr)? {
+        Some((value, origin)) => {
+            let new_expr = match &value {
+                StructuredValue::Scalar(value) => resolve_value(args, value, original_expr, ty),
+                value => structured_to_expr(value, original_expr, ty)
+            };
+            Some(new_expr.map_err(|err| with_origin(err, var_name, origin.as_deref()))?)
+        },
+        None => None
+    };
+    if let Some(part_overrides) = args.part_overrides {
+        // Part variables apply on top of the whole value, whether that is the default or the
+        // value from the item's own variable.
+        let current = new_expr.as_ref().unwrap_or(original_expr);
+        let is_struct = matches!(current, Expr::Struct(_));
+        let is_array = matches!(current, Expr::Array(_) | Expr::Repeat(_));
+        match part_overrides {
+            PartOverrides::Fields if !is_struct => {
+                return Err(syn::Error::new_spanned(current, "The `fields` option requires the default to be a struct expression"));
+            },
+            PartOverrides::Elements if !is_array => {
+                return Err(syn::Error::new_spanned(current, "The `elements` option requires the default to be an array expression"));
+            },
+            PartOverrides::Nested if !is_struct && !is_array => {
+                return Err(syn::Error::new_spanned(current, "The `nested` option requires the default to be a struct or array expression"));
+            },
+            _ => ()
+        }
+        if let Some(overridden) = override_parts(current, ty, var_name, part_overrides, read_env)? {
+            new_expr = Some(overridden);
+        }
+    }
+    // Part variables also count as setting the value.
+    if new_expr.is_none() {
+        if args.required {
+            return Err(syn::Error::new_spanned(original_expr, format!("Environment variable {} is required but not set", var_name)));
+        }
+        if args.sources.as_ref().is_some_and(|sources| !sources.iter().any(|source| matches!(source, SourceSpec::Default(_)))) {
+            return Err(syn::Error::new_spanned(original_expr, format!("None of the sources defines {} and `default` is not a source", var_name)));
+        }
+    }
+    Ok(new_expr)
+}
+
+/// How the parts of a default can be overridden by their own environment variables.
+#[derive(Clone, Copy)]
+enum PartOverrides {
+    /// `{NAME}_{FIELD}` variables for the fields of a struct.
+    Fields,
+    /// `{NAME}_{INDEX}` variables for the elements of an array.
+    Elements,
+    /// `{NAME}__{FIELD}__{INDEX}...` variables recursing through nested structs and arrays.
+    Nested
+}
+
+impl PartOverrides {
+    fn separator(self) -> &'static str {
+        match self {
+            PartOverrides::Fields | PartOverrides::Elements => "_",
+            PartOverrides::Nested => "__"
+        }
+    }
+}
+
+/// Replaces each field of a struct expression or element of an array expression whose variable
+/// is set, or returns `None` if none are set. Other expressions have no parts to override. The
+/// type of the expression, if known, gives the type of array elements; field types are not known.
+fn override_parts<E: ReadEnv>(expr: &Expr, ty: Option<&syn::Type>, var_name: &str, part_overrides: PartOverrides, read_env: &Layered<'_, E>) -> Result<Option<Expr>, syn::Error> {
+    let separator = part_overrides.separator();
+    match expr {
+        Expr::Struct(expr_struct) => {
+            let mut expr_struct = expr_struct.clone();
+            let mut overridden = false;
+            for field in expr_struct.fields.iter_mut() {
+                let field_name = match &field.member {
+                    syn::Member::Named(ident) => syn::ext::IdentExt::unraw(ident).to_string().to_uppercase(),
+                    syn::Member::Unnamed(index) => index.index.to_string()
+                };
+                let field_var_name = format!("{}{}{}", var_name, separator, field_name);
+                if let Some(new_field_expr) = override_part(&field.expr, None, &field_var_name, part_overrides, read_env)? {
+                    field.expr = new_field_expr;
+                    // Shorthand fields such as `Vec2 { x, y }` need the colon to show the new value.
+                    field.colon_token.get_or_insert_with(Default::default);
+                    overridden = true;
+                }
+            }
+            Ok(overridden.then_some(Expr::Struct(expr_struct)))
+        },
+        Expr::Array(_) | Expr::Repeat(_) => override_elements(expr, element_type(ty), var_name, part_overrides, read_env),
+        _ => Ok(None)
+    }
+}
+
+/// Replaces each element of an array or repeat expression whose variable is set. A repeat
+/// expression such as `[0; 16]` is only expanded if an element is replaced.
+fn override_elements<E: ReadEnv>(expr: &Expr, elem_ty: Option<&syn::Type>, var_name: &str, part_overrides: PartOverrides, read_env: &Layered<'_, E>) -> Result<Option<Expr>, syn::Error> {
+    let (attrs, bracket_token, len) = match expr {
+        Expr::Array(array) => (&array.attrs, array.bracket_token, array.elems.len()),
+        Expr::Repeat(repeat) => match &*repeat.len {
+            Expr::Lit(ExprLit { lit: Lit::Int(len), .. }) => (&repeat.attrs, repeat.bracket_token, len.base10_parse()?),
+            len => return Err(syn::Error::new_spanned(len, "Array length must be an integer literal to override single elements"))
+        },
+        _ => return Ok(None)
+    };
+    let elem_at = |index: usize| match expr {
+        Expr::Array(array) => array.elems[index].clone(),
+        Expr::Repeat(repeat) => (*repeat.expr).clone(),
+        _ => unreachable!()
+    };
+    let prefix = format!("{}{}", var_name, part_overrides.separator());
+    let out_of_bounds = |name: &str| syn::Error::new_spanned(expr, format!("Environment variable {} is out of bounds, the default array has {} elements", name, len));
+    // If the environment can list its variables, only the elements with a variable are read.
+    let indices: Vec<usize> = match read_env.var_names(&prefix) {
+        Some(names) => {
+            let mut indices = Vec::new();
+            for name in names {
+                // Nested variables continue after the index, e.g. `BOARD__CHANNELS__1__GAIN`.
+                let index = name[prefix.len()..].split(part_overrides.separator()).next().unwrap_or_default();
+                let Some(index) = index.parse::<usize>().ok().filter(|parsed| parsed.to_string() == index) else {
+                    continue;
+                };
+                if index >= len {
+                    return Err(out_of_bounds(&name));
+                }
+                indices.push(index);
+            }
+            indices.sort_unstable();
+            indices.dedup();
+            indices
+        },
+        None => {
+            // Without a listing only the index right after the end can be checked.
+            let past_end_var_name = format!("{}{}", prefix, len);
+            if read_env.read_env(&past_end_var_name).is_some() {
+                return Err(out_of_bounds(&past_end_var_name));
+            }
+            (0..len).collect()
+        }
+    };
+    let mut new_elems = Vec::new();
+    for index in indices {
+        let elem_var_name = format!("{}{}", prefix, index);
+        if let Some(new_elem) = override_part(&elem_at(index), elem_ty, &elem_var_name, part_overrides, read_env)? {
+            new_elems.push((index, new_elem));
+        }
+    }
+    if new_elems.is_empty() {
+        return Ok(None);
+    }
+    let mut elems: Vec<Expr> = (0..len).map(elem_at).collect();
+    for (index, new_elem) in new_elems {
+        elems[index] = new_elem;
+    }
+    Ok(Some(Expr::Array(syn::ExprArray {
+        attrs: attrs.clone(),
+        bracket_token,
+        elems: elems.into_iter().collect()
+    })))
+}
+
+/// Computes the replacement of a single field or element from its own variable and, when
+/// nesting, the variables of its parts.
+fn override_part<E: ReadEnv>(part_expr: &Expr, part_ty: Option<&syn::Type>, part_var_name: &str, part_overrides: PartOverrides, read_env: &Layered<'_, E>) -> Result<Option<Expr>, syn::Error> {
+    let mut new_part_expr = match read_env.read_env(part_var_name) {
+        Some(value) => Some(value_to_literal(&value, part_expr, part_ty)
+            .map_err(|err| with_origin(err, part_var_name, read_env.origin_of(part_var_name)))?),
+        None => None
+    };
+    if let PartOverrides::Nested = part_overrides {
+        let current = new_part_expr.as_ref().unwrap_or(part_expr);
+        if let Some(nested) = override_parts(current, part_ty, part_var_name, part_overrides, read_env)? {
+            new_part_expr = Some(nested);
+        }
+    }
+    Ok(new_part_expr)
+}
+
+/// Converts an environment variable value into the expression that replaces the default, applying
+/// any validation requested through the item arguments.
+fn resolve_value(args: &ItemArgs, value: &str, original_expr: &Expr, ty: Option<&syn::Type>) -> Result<Expr, syn::Error> {
+    if let Some(variants) = &args.variants {
+        // Flag sets are validated name by name, which is the same as checking the whole value
+        // for a single enum variant.
+        for name in split_flags(value) {
+            check_variant(name, variants)?;
+        }
+    }
+    if let Some(template) = &args.template {
+        return expand_template(value, template, args.split.as_ref());
+    }
+    if let Some(index) = args.arg {
+        // Only the chosen argument of a call such as `AtomicU32::new(100)` is replaced, and its
+        // type is not known, so it must be replaced by the same kind of literal.
+        let Expr::Call(call) = original_expr else {
+            return Err(syn::Error::new_spanned(original_expr, "The `arg` option requires the default to be a function call"));
+        };
+        let mut call = call.clone();
+        let arg_count = call.args.len();
+        let arg = call.args.iter_mut().nth(index)
+            .ok_or_else(|| syn::Error::new_spanned(original_expr, format!("The `arg` option selects argument {} but the call only has {} arguments", index, arg_count)))?;
+        *arg = match &args.choices {
+            Some(choices) => choice_to_expr(select_choice(value.trim(), choices)?),
+            None => checked_value_to_literal(value, arg, None)?
+        };
+        return Ok(Expr::Call(call));
+    }
+    if let Some(choices) = &args.choices {
+        return select_choice(value.trim(), choices).map(choice_to_expr);
+    }
+    if args.flags {
+        // A single flag such as `Flags::A` can't be told apart from an enum variant without it.
+        let Some(flags_type) = flags_type(original_expr) else {
+            return Err(syn::Error::new_spanned(original_expr, "The `flags` option requires the default to be a flag such as `Flags::A`, a `union` of flags or `Flags::empty()`"));
+        };
+        return flags_to_literal(value, &flags_type, original_expr);
+    }
+    value_to_literal(value, original_expr, ty)
+}
+
+/// How the contents of an environment variable are written, given by the `format` option.
+#[derive(Clone, Copy)]
+enum ValueFormat {
+    Json
+}
+
+/// A value read for an item, either a single value as found in an environment variable, or an array
+/// or table from a configuration file or JSON which is converted into the shape of the default.
+enum StructuredValue {
+    Scalar(String),
+    Array(Vec<StructuredValue>),
+    Table(Vec<(String, StructuredValue)>),
+    Null
+}
+
+impl StructuredValue {
+    /// A string which was already unescaped by a parser. Single values are converted like
+    /// environment variable contents, which may contain escapes, so it is escaped again.
+    fn unescaped(value: &str) -> StructuredValue {
+        StructuredValue::Scalar(value.escape_debug().to_string())
+    }
+}
+
+/// Reads the value of an item from the first of its sources that defines it, along with where it
+/// came from if that is not the environment. Errors point at `spanned`, the default of the item.
+fn read_value<E: ReadEnv>(args: &ItemArgs, var_name: &str, sources: &Layered<'_, E>, spanned: &impl ToTokens) -> Result<Option<(StructuredValue, Option<String>)>, syn::Error> {
+    for source in &sources.sources {
+        let Some(value) = source.read_env.read_env(var_name) else {
+            continue;
+        };
+        let value = match source.format {
+            Some(ValueFormat::Json) => parse_json(&value).map_err(|err| {
+                let described = match &source.origin {
+                    Some(origin) => format!("{} from {}", var_name, origin),
+                    None => format!("environment variable {}", var_name)
+                };
+                syn::Error::new_spanned(spanned, format!("Failed to parse {} as JSON: {}", described, err))
+            })?,
+            None => StructuredValue::Scalar(value)
+        };
+        return Ok(Some((value, source.origin.clone())));
+    }
+    // A missing key is an error unless `default` is one of the sources.
+    let default_source = args.sources.as_ref()
+        .is_some_and(|sources| sources.iter().any(|source| matches!(source, SourceSpec::Default(_))));
+    if let Some(key) = &args.key
+        && !default_source {
+        // Say which sources were searched, both for the key and for the variable itself.
+        let origins = |keyed: bool| sources.sources.iter()
+            .filter(|source| source.keyed == keyed)
+            .map(|source| source.origin.as_deref().unwrap_or("the environment"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut message = format!("Key `{}` not found in {}", key.value(), origins(true));
+        let unkeyed = origins(false);
+        if !unkeyed.is_empty() {
+            message.push_str(&format!(", and {} is not set in {}", var_name, unkeyed));
+        }
+        return Err(syn::Error::new_spanned(key, message));
+    }
+    Ok(None)
+}
+
+/// Converts an array or table value into the shape of the default, e.g. a table into a struct
+/// expression with the same path and fields. Single values are converted like environment
+/// variable contents.
+fn structured_to_expr(value: &StructuredValue, original_expr: &Expr, ty: Option<&syn::Type>) -> Result<Expr, syn::Error> {
+    match (value, original_expr) {
+        (StructuredValue::Scalar(value), _) => value_to_literal(value, original_expr, ty),
+        (StructuredValue::Null, _) if is_option_default(original_expr) || ty.and_then(option_inner_type).is_some() => {
+            Ok(syn::parse_quote_spanned!(original_expr.span() => None))
+        },
+        (StructuredValue::Null, _) => Err(syn::Error::new_spanned(original_expr, "A null value requires an `Option` default")),
+        (_, Expr::Reference(reference)) => {
+            let elem_ty = match ty {
+                Some(syn::Type::Reference(ty)) => Some(&*ty.elem),
+                _ => None
+            };
+            let mut reference = reference.clone();
+            *reference.expr = structured_to_expr(value, &reference.expr, elem_ty)?;
+            Ok(Expr::Reference(reference))
+        },
+        (_, Expr::Call(call)) if matches!(&*call.func, Expr::Path(func) if is_option_variant(&func.path, "Some")) && call.args.len() == 1 => {
+            let mut call = call.clone();
+            let arg = call.args.first_mut().unwrap();
+            *arg = structured_to_expr(value, arg, ty.and_then(option_inner_type))?;
+            Ok(Expr::Call(call))
+        },
+        (StructuredValue::Array(values), Expr::Array(_) | Expr::Repeat(_)) => {
+            // Every element is converted like the first element of the default.
+            let prototype = match original_expr {
+                Expr::Array(array) => array.elems.first()
+                    .ok_or_else(|| syn::Error::new_spanned(array, "An empty array default doesn't show how to convert the elements of an array value"))?,
+                Expr::Repeat(repeat) => &repeat.expr,
+                _ => unreachable!()
+            };
+            let elems = values.iter()
+                .map(|value| structured_to_expr(value, prototype, element_type(ty)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(syn::parse_quote_spanned!(original_expr.span() => [#(#elems),*]))
+        },
+        (StructuredValue::Array(values), Expr::Tuple(tuple)) => {
+            if values.len() != tuple.elems.len() {
+                return Err(syn::Error::new_spanned(tuple, format!("Expected {} values for the tuple default but found {}", tuple.elems.len(), values.len())));
+            }
+            let elem_tys: Vec<Option<&syn::Type>> = match ty {
+                Some(syn::Type::Tuple(ty)) => ty.elems.iter().map(Some).collect(),
+                _ => vec![None; values.len()]
+            };
+            let mut tuple = tuple.clone();
+            for ((elem, value), elem_ty) in tuple.elems.iter_mut().zip(values).zip(elem_tys) {
+                *elem = structured_to_expr(value, elem, elem_ty)?;
+            }
+            Ok(Expr::Tuple(tuple))
+        },
+        (StructuredValue::Table(entries), Expr::Struct(original)) => {
+            // Fields missing from the table keep their default values.
+            let mut new = original.clone();
+            for (key, value) in entries {
+                let field = new.fields.iter_mut()
+                    .find(|field| matches!(&field.member, syn::Member::Named(ident) if syn::ext::IdentExt::unraw(ident) == key))
+                    .ok_or_else(|| syn::Error::new_spanned(original, format!("The default has no field `{}`", key)))?;
+                field.expr = structured_to_expr(value, &field.expr, None)?;
+                field.colon_token.get_or_insert_with(Default::default);
+            }
+            Ok(Expr::Struct(new))
+        },
+        (StructuredValue::Array(_), _) => Err(syn::Error::new_spanned(original_expr, "An array value requires an array or tuple default")),
+        (StructuredValue::Table(_), _) => Err(syn::Error::new_spanned(original_expr, "A table value requires a struct default"))
+    }
+}
+
+/// Whether the default is `None` or `Some(..)`.
+fn is_option_default(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(path) => is_option_variant(&path.path, "None"),
+        Expr::Call(call) => matches!(&*call.func, Expr::Path(func) if is_option_variant(&func.path, "Some")),
+        _ => false
+    }
+}
+
+/// Parses JSON into a structured value. Numbers keep their text, so they are converted exactly like
+/// numbers in environment variables.
+fn parse_json(json: &str) -> Result<StructuredValue, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    Ok(json_to_structured(value))
+}
+
+fn json_to_structured(value: serde_json::Value) -> StructuredValue {
+    match value {
+        serde_json::Value::Null => StructuredValue::Null,
+        serde_json::Value::Bool(value) => StructuredValue::Scalar(value.to_string()),
+        serde_json::Value::Number(value) => StructuredValue::Scalar(value.to_string()),
+        serde_json::Value::String(value) => StructuredValue::unescaped(&value),
+        serde_json::Value::Array(values) => StructuredValue::Array(values.into_iter().map(json_to_structured).collect()),
+        serde_json::Value::Object(entries) => StructuredValue::Table(entries.into_iter()
+            .map(|(key, value)| (key, json_to_structured(value)))
+            .collect())
+    }
+}
+
+/// Substitutes the pieces of a value into placeholders such as `{0}` in a template expression.
+/// Each piece becomes a single literal, so the value cannot inject any other tokens.
+fn expand_template(value: &str, template: &syn::LitStr, split: Option<&syn::LitStr>) -> Result<Expr, syn::Error> {
+    let pieces: Vec<&str> = match split {
+        Some(split) => value.split(split.value().as_str()).collect(),
+        None => vec![value]
+    };
+    let template_value = template.value();
+    let mut expanded = String::new();
+    let mut used = vec![false; pieces.len()];
+    let mut chars = template_value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                expanded.push('{');
+            },
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                expanded.push('}');
+            },
+            '{' => {
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(digit) if digit.is_ascii_digit() => index.push(digit),
+                        _ => return Err(syn::Error::new_spanned(template, "Template placeholders must be of the form `{N}`, use `{{` and `}}` for literal braces"))
+                    }
+                }
+                let index: usize = index.parse()
+                    .map_err(|_| syn::Error::new_spanned(template, "Template placeholders must be of the form `{N}`, use `{{` and `}}` for literal braces"))?;
+                let piece = pieces.get(index)
+                    .ok_or_else(|| syn::Error::new_spanned(template, format!("Template placeholder `{{{}}}` has no matching value, environment variable contents have {} values", index, pieces.len())))?;
+                used[index] = true;
+                expanded.push_str(&template_literal(piece));
+            },
+            '}' => return Err(syn::Error::new_spanned(template, "Unmatched `}` in template, use `}}` for a literal brace")),
+            c => expanded.push(c)
+        }
+    }
+    if let Some(unused) = used.iter().position(|used| !used) {
+        return Err(syn::Error::new_spanned(template, format!("Environment variable contents have {} values but template placeholder `{{{}}}` is not used", pieces.len(), unused)));
+    }
+    syn::parse_str(&expanded)
+        .map_err(|err| syn::Error::new_spanned(template, format!("Expanded template `{}` is not a valid expression: {}", expanded, err)))
+}
+
+/// Renders one piece of a templated value as a literal. Numbers and booleans are kept as they are
+/// and everything else becomes a string literal.
+fn template_literal(piece: &str) -> String {
+    let piece = piece.trim();
+    let unsigned = piece.strip_prefix('-').unwrap_or(piece);
+    match syn::parse_str::<Lit>(unsigned) {
+        Ok(Lit::Int(_) | Lit::Float(_)) => piece.to_string(),
+        Ok(Lit::Bool(_)) if unsigned == piece => piece.to_string(),
+        _ => syn::LitStr::new(piece, proc_macro2::Span::call_site()).to_token_stream().to_string()
+    }
+}
+
+fn choice_to_expr(choice: &syn::Path) -> Expr {
+    Expr::Path(syn::ExprPath {
+        attrs: Vec::new(),
+        qself: None,
+        path: choice.clone()
+    })
+}
+
+/// Picks the allowlisted path named by a value, which may be the full path or just its last
+/// segment if that is unambiguous. Nothing from the value itself ends up in the expansion.
+fn select_choice<'a>(value: &str, choices: &'a [syn::Path]) -> Result<&'a syn::Path, syn::Error> {
+    let value: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    if let Some(choice) = choices.iter().find(|choice| path_to_string(choice) == value) {
+        return Ok(choice);
+    }
+    let mut by_name = choices.iter()
+        .filter(|choice| choice.segments.last().is_some_and(|last| last.ident == value));
+    if let (Some(choice), None) = (by_name.next(), by_name.next()) {
+        return Ok(choice);
+    }
+    let mut message = format!("Environment variable contents `{}` are not one of the allowed choices", value);
+    if let Some(suggestion) = did_you_mean(&value, choices.iter().map(path_to_string)) {
+        message.push_str(&format!(", did you mean `{}`?", suggestion));
+    }
+    Err(syn::Error::new(proc_macro2::Span::call_site(), message))
+}
+
+fn path_to_string(path: &syn::Path) -> String {
+    path.to_token_stream().to_string().chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn check_variant(name: &str, variants: &[syn::Ident]) -> Result<(), syn::Error> {
+    if variants.iter().any(|variant| variant == name) {
+        return Ok(());
+    }
+    let mut message = format!("Environment variable contents `{}` are not one of the allowed variants", name);
+    if let Some(suggestion) = did_you_mean(name, variants.iter().map(|variant| variant.to_string())) {
+        message.push_str(&format!(", did you mean `{}`?", suggestion));
+    }
+    Err(syn::Error::new(proc_macro2::Span::call_site(), message))
+}
+
+/// Finds the candidate closest to a misspelled name, if any is close enough to be a likely typo.
+fn did_you_mean(name: &str, candidates: impl Iterator<Item = String>) -> Option<String> {
+    candidates
+        .map(|candidate| (edit_distance(&name.to_lowercase(), &candidate.to_lowercase()), candidate))
+        .filter(|(distance, candidate)| *distance <= candidate.chars().count() / 3 + 1)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn extract_var_name_from_expr(expr: &Expr) -> Result<String, syn::Error> {
+    match expr {
+        Expr::Lit(literal) => {
+            match &literal.lit {
+                Lit::Str(lit_str) => {
+                    Ok(lit_str.value())
+                },
+                _ => Err(syn::Error::new_spanned(expr, "Attribute arguments are not a valid string literal"))
+            }
+        },
+        Expr::Paren(paren) => {
+            extract_var_name_from_expr(&paren.expr)
+        },
+        _ => {
+            Err(syn::Error::new_spanned(expr, "Attribute arguments are not a valid string literal expression"))
+        }
+    }
+}
+
+fn value_to_literal(value: &str, original_expr: &Expr, ty: Option<&syn::Type>) -> Result<Expr, syn::Error> {
+    // Struct fields have no declared type, but a default such as `u8::MIN` or `5 as u16` still
+    // shows it.
+    let untyped = ty.is_none();
+    let inferred = if untyped { default_type(original_expr) } else { None };
+    let ty = ty.or(inferred.as_ref());
+    // Paths such as `u32::MAX` or `Self::DEFAULT` don't show what kind of literal is expected, so
+    // the declared type decides instead. Paths of other types are enum variants or flags below.
+    if let Expr::Path(path) = original_expr
+        && !is_option_variant(&path.path, "None") {
+        if let Some(ty) = ty
+            && let Some(new) = value_for_type(value, ty)? {
+            return Ok(new);
+        }
+        // Without a type, a literal can still replace the path since it can't be a variant name.
+        if untyped
+            && let Some(new) = untyped_literal(value) {
+            return Ok(new);
+        }
+    }
+    Ok(match original_expr {
+        Expr::Array(array) => {
+            syn::Expr::Array(syn::parse_str::<syn::ExprArray>(value)
+                .map_err(|_| syn::Error::new_spanned(array, "Failed to parse environment variable contents as valid array"))?)
+        },
+        Expr::Tuple(tuple) => {
+            syn::Expr::Tuple(syn::parse_str::<syn::ExprTuple>(value)
+                .map_err(|_| syn::Error::new_spanned(tuple, "Failed to parse environment variable contents as valid tuple"))?)
+        },
+        Expr::Unary(unary) => {
+            // A unary sign indicates this is a numeric literal which doesn't need any
             // escaping, so we can parse it directly.
             let new: Expr = syn::parse_str(value)
                 .map_err(|_| syn::Error::new_spanned(unary, "Failed to parse environment variable contents as valid expression"))?;
@@ -243,10 +2089,344 @@ fn value_to_literal(value: &str, original_expr: &Expr) -> Result<Expr, syn::Erro
             }.into()
         },
         Expr::Struct(_) => {
-            return Ok(syn::parse_str(value)?)
-        }
+            return syn::parse_str(value);
+        },
+        Expr::Range(range) => {
+            return range_to_literal(value, range, ty.and_then(range_endpoint_type)).map(Expr::Range);
+        },
+        Expr::MethodCall(_) | Expr::Call(_) if is_flags_default(original_expr) => {
+            let flags_type = flags_type(original_expr).unwrap();
+            return flags_to_literal(value, &flags_type, original_expr);
+        },
+        Expr::Path(path) if path.path.segments.len() > 1 && !is_option_variant(&path.path, "None") => {
+            // A path such as `Backend::Postgres` selects an enum variant, so the value replaces
+            // only the last segment and keeps the enum path from the default.
+            let mut variant: syn::Ident = syn::parse_str(value.trim())
+                .map_err(|_| syn::Error::new_spanned(path, format!("Environment variable contents `{}` are not a valid enum variant name{}", value, missing_type_hint(untyped))))?;
+            let mut path = path.clone();
+            let last = path.path.segments.last_mut().unwrap();
+            variant.set_span(last.ident.span());
+            last.ident = variant;
+            Expr::Path(path)
+        },
+        Expr::Path(path) if is_option_variant(&path.path, "None") => {
+            // There is no default literal to go by, so the inner value is parsed according to the
+            // `T` of the declared `Option<T>` type instead.
+            let inner = match ty.and_then(option_inner_type) {
+                Some(inner_ty) => value_for_type(value, inner_ty)?,
+                None if untyped => Some(untyped_literal(value)
+                    .ok_or_else(|| syn::Error::new_spanned(path, format!("Environment variable contents `{}` are not a literal, which is required to replace a `None` default whose type is not known", value)))?),
+                None => None
+            }.ok_or_else(|| syn::Error::new_spanned(path, "A `None` default requires the item to be declared as `Option<T>` where `T` is a literal type"))?;
+            syn::parse_quote_spanned!(path.span() => Some(#inner))
+        },
+        Expr::Call(call) if matches!(&*call.func, Expr::Path(func) if is_option_variant(&func.path, "Some")) && call.args.len() == 1 => {
+            let inner_ty = ty.and_then(option_inner_type);
+            let mut call = call.clone();
+            let arg = call.args.first_mut().unwrap();
+            *arg = value_to_literal(value, arg, inner_ty)?;
+            Expr::Call(call)
+        },
         expr => {
-            return Err(syn::Error::new_spanned(expr, "Original const expression was not a recognized literal expression"));
+            // Other expressions such as `1 << 10` can still be replaced if the declared type is
+            // a literal type.
+            if let Some(ty) = ty
+                && let Some(new) = value_for_type(value, ty)? {
+                return Ok(new);
+            }
+            if untyped
+                && let Some(new) = untyped_literal(value) {
+                return Ok(new);
+            }
+            return Err(syn::Error::new_spanned(expr, format!("Original const expression was not a recognized literal expression{}", missing_type_hint(untyped))));
         }
     })
-}
\ No newline at end of file
+}
+
+/// A hint for `env_lit!` calls without a type, whose default may have been converted by older
+/// versions but doesn't show how to convert the value now.
+fn missing_type_hint(untyped: bool) -> &'static str {
+    if untyped {
+        ", give the type with `env_lit!(\"NAME\" as TYPE, ...)` or use the `raw` option to insert the contents as before"
+    } else {
+        ""
+    }
+}
+
+/// Whether the default is a bitflags value such as `Flags::A.union(Flags::B)` or `Flags::empty()`.
+/// A single `Flags::A` default looks like an enum variant, so it is only treated as flags with the
+/// `flags` option.
+fn is_flags_default(original_expr: &Expr) -> bool {
+    matches!(original_expr, Expr::MethodCall(_) | Expr::Call(_)) && flags_type(original_expr).is_some()
+}
+
+/// Returns the flags type of a bitflags default, e.g. `Flags` for `Flags::A.union(Flags::B)`.
+fn flags_type(expr: &Expr) -> Option<syn::Path> {
+    let path = match expr {
+        Expr::MethodCall(call) if call.method == "union" && call.args.len() == 1 => return flags_type(&call.receiver),
+        Expr::Call(call) if call.args.is_empty() => match &*call.func {
+            Expr::Path(func) if func.path.segments.last().is_some_and(|last| last.ident == "empty" || last.ident == "all") => &func.path,
+            _ => return None
+        },
+        Expr::Path(path) => &path.path,
+        _ => return None
+    };
+    if path.segments.len() < 2 {
+        return None;
+    }
+    Some(syn::Path {
+        leading_colon: path.leading_colon,
+        segments: path.segments.iter().take(path.segments.len() - 1).cloned().collect()
+    })
+}
+
+fn split_flags(value: &str) -> impl Iterator<Item = &str> {
+    value.split(['|', ',']).map(str::trim).filter(|name| !name.is_empty())
+}
+
+/// Expands a value such as `A|C|D` into `Flags::A.union(Flags::C).union(Flags::D)`, or
+/// `Flags::empty()` if no names are given.
+fn flags_to_literal(value: &str, flags_type: &syn::Path, original_expr: &Expr) -> Result<Expr, syn::Error> {
+    let span = original_expr.span();
+    let mut result: Option<Expr> = None;
+    for name in split_flags(value) {
+        let mut flag: syn::Ident = syn::parse_str(name)
+            .map_err(|_| syn::Error::new_spanned(original_expr, format!("Environment variable contents `{}` are not a valid flag name", name)))?;
+        flag.set_span(span);
+        result = Some(match result {
+            None => syn::parse_quote_spanned!(span => #flags_type::#flag),
+            Some(flags) => syn::parse_quote_spanned!(span => #flags.union(#flags_type::#flag))
+        });
+    }
+    Ok(result.unwrap_or_else(|| syn::parse_quote_spanned!(span => #flags_type::empty())))
+}
+
+/// Parses a value according to a declared type, for cases where the default expression does not
+/// show what kind of literal is expected. Returns `None` if the type is not a recognized literal type.
+fn value_for_type(value: &str, ty: &syn::Type) -> Result<Option<Expr>, syn::Error> {
+    let prototype: Expr = match ty {
+        syn::Type::Reference(reference) => match &*reference.elem {
+            syn::Type::Path(path) if path.path.is_ident("str") => syn::parse_quote!(""),
+            syn::Type::Slice(slice) if matches!(&*slice.elem, syn::Type::Path(path) if path.path.is_ident("u8")) => syn::parse_quote!(b""),
+            _ => return Ok(None)
+        },
+        syn::Type::Path(path) if path.qself.is_none() => {
+            if option_inner_type(ty).is_some() {
+                syn::parse_quote!(None)
+            } else if let Some(ident) = path.path.get_ident() {
+                match ident.to_string().as_str() {
+                    "bool" => syn::parse_quote!(false),
+                    "char" => syn::parse_quote!(' '),
+                    "i8" | "i16" | "i32" | "i64" | "i128" | "isize"
+                    | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => syn::parse_quote!(0),
+                    "f32" | "f64" => syn::parse_quote!(0.0),
+                    _ => return Ok(None)
+                }
+            } else {
+                return Ok(None);
+            }
+        },
+        syn::Type::Paren(paren) => return value_for_type(value, &paren.elem),
+        syn::Type::Group(group) => return value_for_type(value, &group.elem),
+        _ => return Ok(None)
+    };
+    value_to_literal(value, &prototype, Some(ty)).map(Some)
+}
+
+/// Guesses the type of a default whose type is not declared, e.g. `u8` for `u8::MIN` or `u16` for
+/// `5 as u16`.
+fn default_type(expr: &Expr) -> Option<syn::Type> {
+    match expr {
+        Expr::Path(path) if path.qself.is_none() && path.path.segments.len() == 2 && !is_option_variant(&path.path, "None") => {
+            let ty = &path.path.segments[0];
+            if !ty.arguments.is_none() {
+                return None;
+            }
+            let ident = &ty.ident;
+            Some(syn::parse_quote!(#ident))
+        },
+        Expr::Cast(cast) => Some((*cast.ty).clone()),
+        Expr::Paren(paren) => default_type(&paren.expr),
+        Expr::Group(group) => default_type(&group.expr),
+        _ => None
+    }
+}
+
+/// Parses a value that replaces a default whose type is not known. Only literals such as `5`,
+/// `-1.5`, `true` or `"text"` are accepted, since there is nothing to tell how to quote anything else.
+fn untyped_literal(value: &str) -> Option<Expr> {
+    let expr: Expr = syn::parse_str(value.trim()).ok()?;
+    match &expr {
+        Expr::Lit(_) => Some(expr),
+        Expr::Unary(unary) if matches!(unary.op, syn::UnOp::Neg(_)) && matches!(&*unary.expr, Expr::Lit(_)) => Some(expr),
+        _ => None
+    }
+}
+
+/// Returns the `T` of an array type `[T; N]` or slice type `[T]`.
+fn element_type(ty: Option<&syn::Type>) -> Option<&syn::Type> {
+    match ty? {
+        syn::Type::Array(array) => Some(&array.elem),
+        syn::Type::Slice(slice) => Some(&slice.elem),
+        syn::Type::Paren(paren) => element_type(Some(&paren.elem)),
+        syn::Type::Group(group) => element_type(Some(&group.elem)),
+        _ => None
+    }
+}
+
+/// Returns the `T` of a range type such as `Range<T>` or `RangeInclusive<T>`.
+fn range_endpoint_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if !segment.ident.to_string().starts_with("Range") {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first()? {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+/// Returns the `T` of an `Option<T>` type.
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first()? {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+/// Whether a path refers to the `Some` or `None` variant of `Option`, either directly or through
+/// `Option::`.
+fn is_option_variant(path: &syn::Path, variant: &str) -> bool {
+    let mut segments = path.segments.iter().rev();
+    match (segments.next(), segments.next()) {
+        (Some(last), None) => last.ident == variant,
+        (Some(last), Some(parent)) => last.ident == variant && parent.ident == "Option",
+        _ => false
+    }
+}
+
+fn range_to_literal(value: &str, original: &syn::ExprRange, endpoint_ty: Option<&syn::Type>) -> Result<syn::ExprRange, syn::Error> {
+    let (start, syntax, end) = split_range(value.trim())
+        .ok_or_else(|| syn::Error::new_spanned(original, "Failed to parse environment variable contents as range, expected `start..end`, `start..=end` or `start-end`"))?;
+    match (syntax, &original.limits) {
+        (RangeSyntax::HalfOpen, syn::RangeLimits::Closed(_)) => {
+            return Err(syn::Error::new_spanned(original, "Environment variable contents must be an inclusive range `start..=end` to match the default value"));
+        },
+        (RangeSyntax::Closed, syn::RangeLimits::HalfOpen(_)) => {
+            return Err(syn::Error::new_spanned(original, "Environment variable contents must be a half-open range `start..end` to match the default value"));
+        },
+        // A dash usually includes the end, as in port spans, so it would be misread as half-open.
+        (RangeSyntax::Dash, syn::RangeLimits::HalfOpen(_)) => {
+            return Err(syn::Error::new_spanned(original, "Environment variable contents must be a half-open range `start..end` to match the default value, the `start-end` shorthand is only accepted for inclusive ranges"));
+        },
+        _ => ()
+    }
+    let new_start = range_endpoint_to_literal(start, original.start.as_deref(), original, endpoint_ty)?;
+    let new_end = range_endpoint_to_literal(end, original.end.as_deref(), original, endpoint_ty)?;
+    if let (Some(start), Some(end)) = (new_start.as_ref().and_then(scalar_value), new_end.as_ref().and_then(scalar_value))
+        && start > end {
+        return Err(syn::Error::new_spanned(original, "Environment variable contents are an invalid range, start is greater than end"));
+    }
+    Ok(syn::ExprRange {
+        attrs: original.attrs.clone(),
+        start: new_start.map(Box::new),
+        limits: original.limits,
+        end: new_end.map(Box::new)
+    })
+}
+
+fn range_endpoint_to_literal(value: &str, original_endpoint: Option<&Expr>, original: &syn::ExprRange, endpoint_ty: Option<&syn::Type>) -> Result<Option<Expr>, syn::Error> {
+    let value = value.trim();
+    let original_endpoint = match (value.is_empty(), original_endpoint) {
+        (true, None) => return Ok(None),
+        (false, Some(original_endpoint)) => original_endpoint,
+        _ => return Err(syn::Error::new_spanned(original, "Environment variable contents must have the same range endpoints as the default value"))
+    };
+    checked_value_to_literal(value, original_endpoint, endpoint_ty).map(Some)
+}
+
+/// Like `value_to_literal`, but additionally requires a numeric or character default to be
+/// replaced by the same kind of literal. Numeric literals are otherwise parsed as arbitrary
+/// expressions.
+fn checked_value_to_literal(value: &str, original_expr: &Expr, ty: Option<&syn::Type>) -> Result<Expr, syn::Error> {
+    let new = value_to_literal(value, original_expr, ty)?;
+    if let Some(original_scalar) = scalar_value(original_expr) {
+        let same_kind = scalar_value(&new)
+            .is_some_and(|new_scalar| std::mem::discriminant(&new_scalar) == std::mem::discriminant(&original_scalar));
+        if !same_kind {
+            return Err(syn::Error::new_spanned(original_expr, format!("Environment variable contents `{}` are not the same kind of literal as the default value", value.trim())));
+        }
+    }
+    Ok(new)
+}
+
+enum RangeSyntax {
+    HalfOpen,
+    Closed,
+    Dash
+}
+
+/// Splits a range such as `1..10`, `1..=10` or `1-10` into its start and end.
+fn split_range(value: &str) -> Option<(&str, RangeSyntax, &str)> {
+    if let Some((start, end)) = value.split_once("..=") {
+        return Some((start, RangeSyntax::Closed, end));
+    }
+    if let Some((start, end)) = value.split_once("..") {
+        return Some((start, RangeSyntax::HalfOpen, end));
+    }
+    // A dash only separates the endpoints if it follows the start value, otherwise it is a
+    // negative sign or part of a float exponent.
+    let mut previous = None;
+    for (index, c) in value.char_indices() {
+        if c == '-' && previous.is_some_and(|p| !matches!(p, '-' | 'e' | 'E')) {
+            return Some((&value[..index], RangeSyntax::Dash, &value[index + 1..]));
+        }
+        if !c.is_whitespace() {
+            previous = Some(c);
+        }
+    }
+    None
+}
+
+#[derive(PartialEq, PartialOrd)]
+enum Scalar {
+    Int(i128),
+    Float(f64),
+    Char(char)
+}
+
+/// Evaluates a numeric or character literal, including negated ones.
+fn scalar_value(expr: &Expr) -> Option<Scalar> {
+    match expr {
+        Expr::Lit(literal) => match &literal.lit {
+            Lit::Int(lit_int) => lit_int.base10_parse().ok().map(Scalar::Int),
+            Lit::Float(lit_float) => lit_float.base10_parse().ok().map(Scalar::Float),
+            Lit::Char(lit_char) => Some(Scalar::Char(lit_char.value())),
+            Lit::Byte(lit_byte) => Some(Scalar::Int(lit_byte.value().into())),
+            _ => None
+        },
+        Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => match scalar_value(expr)? {
+            Scalar::Int(int) => Some(Scalar::Int(-int)),
+            Scalar::Float(float) => Some(Scalar::Float(-float)),
+            Scalar::Char(_) => None
+        },
+        Expr::Paren(paren) => scalar_value(&paren.expr),
+        _ => None
+    }
+}
@@ -0,0 +1,112 @@
+use const_env_impl::{config, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_entries() {
+    let env = TestEnv::builder()
+        .set("PORT", "9090")
+        .set("HOSTNAME", "example.com")
+        .build();
+    let tokens: TokenStream = quote! {
+        pub PORT: u16 = 8080;
+        /// The host name.
+        #[env("HOSTNAME")]
+        HOST: &str = "localhost";
+    };
+    let expected: TokenStream = quote! {
+        pub const PORT: u16 = 9090;
+        /// The host name.
+        const HOST: &str = "example.com";
+        /// The name, environment variable name and value of every constant in this `config!` block.
+        #[allow(dead_code)]
+        pub(crate) const CONFIG_ENTRIES: &[(&str, &str, &str)] = &[("PORT", "PORT", "9090"), ("HOST", "HOSTNAME", "\"example.com\"")];
+    };
+    let result = config(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_shared_prefix_and_listing() {
+    let env = TestEnv::builder()
+        .set("APP_PORT", "9090")
+        .set("PORT", "1")
+        .build();
+    let tokens: TokenStream = quote! {
+        #![env(prefix = "APP_", listing = APP_ENTRIES)]
+        pub PORT: u16 = 8080;
+    };
+    let expected: TokenStream = quote! {
+        pub const PORT: u16 = 9090;
+        /// The name, environment variable name and value of every constant in this `config!` block.
+        #[allow(dead_code)]
+        pub(crate) const APP_ENTRIES: &[(&str, &str, &str)] = &[("PORT", "APP_PORT", "9090")];
+    };
+    let result = config(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_shared_required() {
+    let env = TestEnv::builder()
+        .set("PORT", "9090")
+        .build();
+    let tokens: TokenStream = quote! {
+        #![env(required)]
+        pub PORT: u16 = 8080;
+        pub HOST: &str = "localhost";
+    };
+    let result = config(tokens, env);
+    assert!(format!("{}", result).contains("Environment variable HOST is required but not set"));
+}
+
+#[test]
+fn test_listing_named_after_prefix() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        #![env(prefix = "db.")]
+        pub PORT: u16 = 5432;
+    };
+    let expected: TokenStream = quote! {
+        pub const PORT: u16 = 5432;
+        /// The name, environment variable name and value of every constant in this `config!` block.
+        #[allow(dead_code)]
+        pub(crate) const DB_CONFIG_ENTRIES: &[(&str, &str, &str)] = &[("PORT", "db.PORT", "5432")];
+    };
+    let result = config(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_required_satisfied_by_fields() {
+    let env = TestEnv::builder()
+        .set("ORIGIN_X", "1.0")
+        .build();
+    let tokens: TokenStream = quote! {
+        #![env(required)]
+        #[env(fields)]
+        pub ORIGIN: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+    };
+    let expected: TokenStream = quote! {
+        pub const ORIGIN: Vec2 = Vec2 { x: 1.0, y: 0.0 };
+        /// The name, environment variable name and value of every constant in this `config!` block.
+        #[allow(dead_code)]
+        pub(crate) const CONFIG_ENTRIES: &[(&str, &str, &str)] = &[("ORIGIN", "ORIGIN", "Vec2 { x : 1.0 , y : 0.0 }")];
+    };
+    let result = config(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_shared_name_rejected() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        #![env("PORT")]
+        pub PORT: u16 = 8080;
+    };
+    let result = config(tokens, env);
+    assert!(format!("{}", result).contains("cannot be shared"));
+}
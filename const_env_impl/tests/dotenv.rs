@@ -0,0 +1,195 @@
+use const_env_impl::{env_item, env_lit, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_dotenv_value() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/.env", "# Local settings\nexport PORT=9090 # comment\nHOST='local host'\n")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 8080;
+    };
+    let expected: TokenStream = quote! {
+        const PORT: u16 = 9090;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_real_env_takes_precedence() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .set("GREETING", "from env")
+        .file("/project/.env", "GREETING=\"from \\\"file\\\"\"")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv
+    };
+    let item: TokenStream = quote! {
+        const GREETING: &str = "hi";
+    };
+    let expected: TokenStream = quote! {
+        const GREETING: &str = "from env";
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_quoted_value() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/config/local.env", "GREETING = \"hello # not a comment\" # comment")
+        .build();
+    let tokens: TokenStream = quote! {
+        "GREETING", "hi", dotenv = "config/local.env"
+    };
+    let expected: TokenStream = quote! {
+        "hello # not a comment"
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_escaped_value() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/.env", "GREETING=\"say \\\"hi\\\"\"\nDIR=\"C:\\\\dir\"")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv
+    };
+    let item: TokenStream = quote! {
+        const GREETING: &str = "hi";
+    };
+    let expected: TokenStream = quote! {
+        const GREETING: &str = "say \"hi\"";
+    };
+    let result = env_item(attr.clone(), item, &env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+    let item: TokenStream = quote! {
+        const DIR: &str = "";
+    };
+    let expected: TokenStream = quote! {
+        const DIR: &str = "C:\\dir";
+    };
+    let result = env_item(attr, item, &env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_optional_dotenv_missing() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 8080;
+    };
+    let result = env_item(attr, item.clone(), env);
+    assert_eq!(format!("{}", item), format!("{}", result));
+}
+
+#[test]
+fn test_explicit_dotenv_missing() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv = "local.env"
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 8080;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Failed to read dotenv file /project/local.env"));
+}
+
+#[test]
+fn test_crate_wide_dotenv() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .set("CONST_ENV_DOTENV", "build.env")
+        .file("/project/build.env", "PORT=9090")
+        .build();
+    let tokens: TokenStream = quote! {
+        "PORT", 8080
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!("9090", format!("{}", result));
+}
+
+#[test]
+fn test_invalid_dotenv() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/.env", "PORT=9090\nHOST\n")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 8080;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("line 2: expected `NAME=value`"));
+}
+
+#[test]
+fn test_origin_in_diagnostics() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/.env", "BACKEND=Sqlte\n")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv, variants = [Postgres, Sqlite]
+    };
+    let item: TokenStream = quote! {
+        const BACKEND: Backend = Backend::Postgres;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("(value of BACKEND from dotenv file /project/.env)"));
+}
+
+#[test]
+fn test_field_origin_in_diagnostics() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/.env", "SERVER_HOST=localhost\n")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv, fields
+    };
+    let item: TokenStream = quote! {
+        const SERVER: Server = Server { host: None, port: 8080 };
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("(value of SERVER_HOST from dotenv file /project/.env)"));
+}
+
+#[test]
+fn test_missing_key_lists_dotenv() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/.env", "HOST=localhost\n")
+        .file("/project/build.toml", "port = 9000\n")
+        .build();
+    let attr: TokenStream = quote! {
+        dotenv, file = "build.toml", key = "timeout"
+    };
+    let item: TokenStream = quote! {
+        const TIMEOUT: u32 = 30;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Key `timeout` not found in configuration file /project/build.toml, and TIMEOUT is not set in the environment, dotenv file /project/.env"));
+}
@@ -0,0 +1,79 @@
+use const_env_impl::{env_cfg, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_equals() {
+    let env = TestEnv::builder()
+        .set("TELEMETRY", "on")
+        .build();
+    let attr: TokenStream = quote! {
+        "TELEMETRY" = "on"
+    };
+    let item: TokenStream = quote! {
+        fn report() {}
+    };
+    let result = env_cfg(attr, item.clone(), env);
+    assert_eq!(format!("{}", item), format!("{}", result));
+}
+
+#[test]
+fn test_equals_other_value() {
+    let env = TestEnv::builder()
+        .set("TELEMETRY", "off")
+        .build();
+    let attr: TokenStream = quote! {
+        "TELEMETRY" = "on"
+    };
+    let item: TokenStream = quote! {
+        fn report() {}
+    };
+    let result = env_cfg(attr, item, env);
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_defined() {
+    let env = TestEnv::builder()
+        .set("DEBUG_HOOKS", "")
+        .build();
+    let attr: TokenStream = quote! {
+        defined("DEBUG_HOOKS")
+    };
+    let item: TokenStream = quote! {
+        mod hooks {}
+    };
+    let result = env_cfg(attr, item.clone(), env);
+    assert_eq!(format!("{}", item), format!("{}", result));
+}
+
+#[test]
+fn test_combinators() {
+    let env = TestEnv::builder()
+        .set("TELEMETRY", "on")
+        .set("DEBUG_HOOKS", "1")
+        .build();
+    let attr: TokenStream = quote! {
+        all("TELEMETRY" = "on", not(defined("DEBUG_HOOKS")))
+    };
+    let item: TokenStream = quote! {
+        impl Reporter for Server {}
+    };
+    let result = env_cfg(attr, item, env);
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_unknown_predicate() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = quote! {
+        set("TELEMETRY")
+    };
+    let item: TokenStream = quote! {
+        fn report() {}
+    };
+    let result = env_cfg(attr, item, env);
+    assert!(format!("{}", result).contains("Unknown predicate `set`"));
+}
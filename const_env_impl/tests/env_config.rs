@@ -0,0 +1,77 @@
+use const_env_impl::{env_config, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_named_fields() {
+    let env = TestEnv::builder()
+        .set("PORT", "9090")
+        .build();
+    let item: TokenStream = quote! {
+        struct Config {
+            #[env(default = 8080)]
+            port: u16,
+            #[env("HOSTNAME", default = "localhost")]
+            host: &'static str,
+        }
+    };
+    let expected: TokenStream = quote! {
+        impl Config {
+            pub const FROM_ENV: Self = Self { port: 9090, host: "localhost" };
+        }
+    };
+    let result = env_config(item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_explicit_name() {
+    let env = TestEnv::builder()
+        .set("HOSTNAME", "example.com")
+        .build();
+    let item: TokenStream = quote! {
+        struct Config {
+            #[env("HOSTNAME", default = "localhost")]
+            host: &'static str,
+        }
+    };
+    let expected: TokenStream = quote! {
+        impl Config {
+            pub const FROM_ENV: Self = Self { host: "example.com" };
+        }
+    };
+    let result = env_config(item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_tuple_struct_with_generics() {
+    let env = TestEnv::builder()
+        .set("SCALE", "2.5")
+        .build();
+    let item: TokenStream = quote! {
+        struct Scale<T>(#[env("SCALE", default = 1.0)] f32, #[env("UNIT", default = None)] Option<T>);
+    };
+    let expected: TokenStream = quote! {
+        impl<T> Scale<T> {
+            pub const FROM_ENV: Self = Self { 0: 2.5, 1: None };
+        }
+    };
+    let result = env_config(item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_missing_default() {
+    let env = TestEnv::builder()
+        .build();
+    let item: TokenStream = quote! {
+        struct Config {
+            #[env("PORT")]
+            port: u16,
+        }
+    };
+    let result = env_config(item, env);
+    assert!(format!("{}", result).contains("Missing `default = ...` option"));
+}
@@ -0,0 +1,28 @@
+use const_env_impl::{env_defined, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_defined() {
+    let env = TestEnv::builder()
+        .set("VERBOSE_BUILD", "")
+        .build();
+    let tokens: TokenStream = quote! {
+        "VERBOSE_BUILD"
+    };
+    let result = env_defined(tokens, env);
+    assert_eq!("true", format!("{}", result));
+}
+
+#[test]
+fn test_not_defined() {
+    let env = TestEnv::builder()
+        .set("VERBOSE", "1")
+        .build();
+    let tokens: TokenStream = quote! {
+        "VERBOSE_BUILD"
+    };
+    let result = env_defined(tokens, env);
+    assert_eq!("false", format!("{}", result));
+}
@@ -0,0 +1,182 @@
+use const_env_impl::{env_lit, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_untyped_path_default() {
+    let env = TestEnv::builder()
+        .set("NAME", "alice")
+        .build();
+    let tokens: TokenStream = quote! {
+        "NAME", DEFAULT_NAME
+    };
+    let result = env_lit(tokens, env);
+    assert!(format!("{}", result).contains("not a recognized literal expression, give the type with"));
+    assert!(format!("{}", result).contains("or use the `raw` option to insert the contents as before"));
+}
+
+#[test]
+fn test_untyped_type_path_default() {
+    let env = TestEnv::builder()
+        .set("LIMIT", "5")
+        .build();
+    let tokens: TokenStream = quote! {
+        "LIMIT", u8::MAX
+    };
+    let expected: TokenStream = quote! {
+        5
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_untyped_cast_default() {
+    let env = TestEnv::builder()
+        .set("LIMIT", "5")
+        .build();
+    let tokens: TokenStream = quote! {
+        "LIMIT", 1 as u16
+    };
+    let expected: TokenStream = quote! {
+        5
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_raw() {
+    let env = TestEnv::builder()
+        .set("NAME", "alice")
+        .build();
+    let tokens: TokenStream = quote! {
+        "NAME", DEFAULT_NAME, raw
+    };
+    let expected: TokenStream = quote! {
+        alice
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_raw_with_options() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        "NAME", DEFAULT_NAME, raw, variants = [Alice, Bob]
+    };
+    let result = env_lit(tokens, env);
+    assert!(format!("{}", result).contains("The `raw` option cannot be combined"));
+}
+
+#[test]
+fn test_struct() {
+    let env = TestEnv::builder()
+        .set("ORIGIN", "Vec2 { x: 1.0, y: -2.5 }")
+        .build();
+    let tokens: TokenStream = quote! {
+        "ORIGIN", Vec2 { x: 0.0, y: 0.0 }
+    };
+    let expected: TokenStream = quote! {
+        Vec2 { x: 1.0, y: -2.5 }
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_tuple() {
+    let env = TestEnv::builder()
+        .set("LIMITS", "(-1, 10)")
+        .build();
+    let tokens: TokenStream = quote! {
+        "LIMITS", (0, 0)
+    };
+    let expected: TokenStream = quote! {
+        (-1, 10)
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_options() {
+    let env = TestEnv::builder()
+        .set("BACKEND", "Sqlte")
+        .build();
+    let tokens: TokenStream = quote! {
+        "BACKEND", Backend::Postgres, variants = [Postgres, Sqlite]
+    };
+    let result = env_lit(tokens, env);
+    assert!(format!("{}", result).contains("did you mean `Sqlite`?"));
+}
+
+#[test]
+fn test_required() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        "PORT", 8080, required
+    };
+    let result = env_lit(tokens, env);
+    assert!(format!("{}", result).contains("Environment variable PORT is required but not set"));
+}
+
+#[test]
+fn test_cast_type() {
+    let env = TestEnv::builder()
+        .set("NAME", "alice")
+        .build();
+    let tokens: TokenStream = quote! {
+        "NAME" as &str, DEFAULT_NAME
+    };
+    let expected: TokenStream = quote! {
+        "alice"
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_leading_type() {
+    let env = TestEnv::builder()
+        .set("PORT", "9000")
+        .build();
+    let tokens: TokenStream = quote! {
+        u16, "PORT", DEFAULT_PORT
+    };
+    let expected: TokenStream = quote! {
+        9000
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_typed_default() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        u16, "PORT", 8080
+    };
+    let expected: TokenStream = quote! {
+        8080
+    };
+    let result = env_lit(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_typed_invalid_value() {
+    let env = TestEnv::builder()
+        .set("NAME", "say \"hi\"")
+        .build();
+    let tokens: TokenStream = quote! {
+        "NAME" as &str, DEFAULT_NAME
+    };
+    let result = env_lit(tokens, env);
+    assert!(format!("{}", result).contains("Failed to parse environment variable contents as literal string"));
+}
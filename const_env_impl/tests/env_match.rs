@@ -0,0 +1,104 @@
+use const_env_impl::{env_match, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_selects_arm() {
+    let env = TestEnv::builder()
+        .set("BACKEND", "sqlite")
+        .build();
+    let tokens: TokenStream = quote! {
+        "BACKEND", {
+            "pg" | "postgres" => PgDriver::new(),
+            "sqlite" => SqliteDriver::new(),
+            _ => NullDriver
+        }
+    };
+    let expected: TokenStream = quote! {
+        SqliteDriver::new()
+    };
+    let result = env_match(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_alternative_pattern() {
+    let env = TestEnv::builder()
+        .set("BACKEND", "postgres")
+        .build();
+    let tokens: TokenStream = quote! {
+        "BACKEND", {
+            "pg" | "postgres" => { PgDriver::new() }
+            _ => NullDriver,
+        }
+    };
+    let expected: TokenStream = quote! {
+        { PgDriver::new() }
+    };
+    let result = env_match(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_unset_uses_wildcard() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        "BACKEND", {
+            "pg" => PgDriver::new(),
+            _ => NullDriver
+        }
+    };
+    let expected: TokenStream = quote! {
+        NullDriver
+    };
+    let result = env_match(tokens, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_no_match() {
+    let env = TestEnv::builder()
+        .set("BACKEND", "sqlit")
+        .build();
+    let tokens: TokenStream = quote! {
+        "BACKEND", {
+            "pg" => PgDriver::new(),
+            "sqlite" => SqliteDriver::new()
+        }
+    };
+    let result = env_match(tokens, env);
+    let result = format!("{}", result);
+    assert!(result.contains("matches no arm"));
+    assert!(result.contains("expected one of: pg, sqlite, did you mean `sqlite`?"));
+}
+
+#[test]
+fn test_duplicate_wildcard() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        "BACKEND", {
+            "pg" => PgDriver::new(),
+            _ => SqliteDriver::new(),
+            _ => MemoryDriver::new()
+        }
+    };
+    let result = env_match(tokens, env);
+    assert!(format!("{}", result).contains("Duplicate `_` arm"));
+}
+
+#[test]
+fn test_arm_after_wildcard() {
+    let env = TestEnv::builder()
+        .build();
+    let tokens: TokenStream = quote! {
+        "BACKEND", {
+            _ => SqliteDriver::new(),
+            "pg" => PgDriver::new()
+        }
+    };
+    let result = env_match(tokens, env);
+    assert!(format!("{}", result).contains("Unreachable arm"));
+}
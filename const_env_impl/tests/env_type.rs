@@ -0,0 +1,94 @@
+use const_env_impl::{env_type, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_choice() {
+    let env = TestEnv::builder()
+        .set("FLOAT", "f64")
+        .build();
+    let attr: TokenStream = quote! {
+        "FLOAT", choices = [f32, f64]
+    };
+    let item: TokenStream = quote! {
+        pub type Float = f32;
+    };
+    let expected: TokenStream = quote! {
+        pub type Float = f64;
+    };
+    let result = env_type(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_default_name() {
+    let env = TestEnv::builder()
+        .set("SAMPLE_INT", "[i16; 2]")
+        .build();
+    let attr: TokenStream = TokenStream::new();
+    let item: TokenStream = quote! {
+        type SampleInt = i32;
+    };
+    let expected: TokenStream = quote! {
+        type SampleInt = [i16; 2];
+    };
+    let result = env_type(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_rejected_choice() {
+    let env = TestEnv::builder()
+        .set("FLOAT", "f16")
+        .build();
+    let attr: TokenStream = quote! {
+        choices = [f32, f64]
+    };
+    let item: TokenStream = quote! {
+        type Float = f32;
+    };
+    let result = env_type(attr, item, env);
+    assert!(format!("{}", result).contains("not one of the allowed choices"));
+}
+
+#[test]
+fn test_value_option_rejected() {
+    let env = TestEnv::builder()
+        .set("FLOAT", "f64")
+        .build();
+    let attr: TokenStream = quote! {
+        "FLOAT", template = "{0}"
+    };
+    let item: TokenStream = quote! {
+        type Float = f32;
+    };
+    let result = env_type(attr, item, env);
+    assert!(format!("{}", result).contains("The `template` option is not valid on type aliases"));
+}
+
+#[test]
+fn test_part_option_rejected() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = quote! {
+        elements
+    };
+    let item: TokenStream = quote! {
+        type Samples = [i16; 4];
+    };
+    let result = env_type(attr, item, env);
+    assert!(format!("{}", result).contains("The `elements` option is not valid on type aliases"));
+}
+
+#[test]
+fn test_not_type_alias() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = TokenStream::new();
+    let item: TokenStream = quote! {
+        const FLOAT: f32 = 0.0;
+    };
+    let result = env_type(attr, item, env);
+    assert!(format!("{}", result).contains("only valid on type aliases"));
+}
@@ -0,0 +1,168 @@
+use const_env_impl::{env_item, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+const BUILD_TOML: &str = r#"
+max_connections = 100
+
+[server]
+port = 9000
+origin = { x = 1.0, y = -2.5 }
+hosts = ["a.example.com", "b.example.com"]
+backend = "Sqlite"
+data_dir = 'C:\data\"const_env"'
+"#;
+
+fn env() -> TestEnv {
+    TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/config/build.toml", BUILD_TOML)
+        .build()
+}
+
+#[test]
+fn test_scalar() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.port"
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 8080;
+    };
+    let expected: TokenStream = quote! {
+        const PORT: u16 = 9000;
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_default_key() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml"
+    };
+    let item: TokenStream = quote! {
+        const MAX_CONNECTIONS: u32 = 10;
+    };
+    let expected: TokenStream = quote! {
+        const MAX_CONNECTIONS: u32 = 100;
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_env_overrides_file() {
+    let env = TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .set("PORT", "7000")
+        .file("/project/config/build.toml", BUILD_TOML)
+        .build();
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.port"
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 8080;
+    };
+    let expected: TokenStream = quote! {
+        const PORT: u16 = 7000;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_table() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.origin"
+    };
+    let item: TokenStream = quote! {
+        const ORIGIN: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+    };
+    let expected: TokenStream = quote! {
+        const ORIGIN: Vec2 = Vec2 { x: 1.0, y: -2.5 };
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_array() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.hosts"
+    };
+    let item: TokenStream = quote! {
+        const HOSTS: &[&str] = &["localhost"];
+    };
+    let expected: TokenStream = quote! {
+        const HOSTS: &[&str] = &["a.example.com", "b.example.com"];
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_escaped_string() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.data_dir"
+    };
+    let item: TokenStream = quote! {
+        const DATA_DIR: &str = "/var/lib/app";
+    };
+    let expected: TokenStream = quote! {
+        const DATA_DIR: &str = "C:\\data\\\"const_env\"";
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_variants() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.backend", variants = [Postgres, Sqlite]
+    };
+    let item: TokenStream = quote! {
+        const BACKEND: Backend = Backend::Postgres;
+    };
+    let expected: TokenStream = quote! {
+        const BACKEND: Backend = Backend::Sqlite;
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_unknown_field() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.origin"
+    };
+    let item: TokenStream = quote! {
+        const ORIGIN: Vec3 = Vec3 { x: 0.0, z: 0.0 };
+    };
+    let result = env_item(attr, item, env());
+    assert!(format!("{}", result).contains("The default has no field `y`"));
+}
+
+#[test]
+fn test_missing_key() {
+    let attr: TokenStream = quote! {
+        file = "config/build.toml", key = "server.timeout"
+    };
+    let item: TokenStream = quote! {
+        const TIMEOUT: u32 = 30;
+    };
+    let result = env_item(attr, item, env());
+    assert!(format!("{}", result).contains("Key `server.timeout` not found in configuration file /project/config/build.toml"));
+}
+
+#[test]
+fn test_missing_file() {
+    let attr: TokenStream = quote! {
+        file = "build.toml"
+    };
+    let item: TokenStream = quote! {
+        const TIMEOUT: u32 = 30;
+    };
+    let result = env_item(attr, item, env());
+    assert!(format!("{}", result).contains("Failed to read configuration file /project/build.toml"));
+}
@@ -1,7 +1,9 @@
-use const_env_impl::{env_item, TestEnv};
+use std::cell::Cell;
+
+use const_env_impl::{env_item, ReadEnv, TestEnv};
 
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 
 #[test]
 fn test_str() {
@@ -359,4 +361,1012 @@ fn test_i32_negative_whitespace() {
     };
     let result = env_item(attr, item, env);
     assert_eq!(format!("{}", expected), format!("{}", result));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_range_inclusive() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "9000..=9100")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: RangeInclusive<u16> = 8000..=8100;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: RangeInclusive<u16> = 9000..=9100;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_range_dash() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "-5--1")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: RangeInclusive<i32> = 0..=10;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: RangeInclusive<i32> = -5..=-1;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_range_dash_half_open() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1-5")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Range<u8> = 0..10;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("the `start-end` shorthand is only accepted for inclusive ranges"));
+}
+
+#[test]
+fn test_range_path_endpoints() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1..=200")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: RangeInclusive<u8> = u8::MIN..=MAX_LEVEL;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: RangeInclusive<u8> = 1..=200;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_range_start_greater_than_end() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "10..=1")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: RangeInclusive<u8> = 0..=255;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("start is greater than end"));
+}
+
+#[test]
+fn test_range_endpoint_wrong_kind() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1.5..=2")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: RangeInclusive<u8> = 0..=255;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Environment variable contents `1.5` are not the same kind of literal as the default value"));
+}
+
+#[test]
+fn test_option_none_str() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "proxy.local")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Option<&'static str> = None;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Option<&'static str> = Some("proxy.local");
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_option_some_i32_negative() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "-5")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Option<i32> = Some(10);
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Option<i32> = Some(-5);
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_option_none_unset() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Option<u32> = None;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Option<u32> = None;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_option_none_without_option_type() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Maybe<u32> = None;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("compile_error"));
+}
+
+#[test]
+fn test_enum_variant() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "Sqlite")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Backend = Backend::Postgres;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Backend = Backend::Sqlite;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_enum_variant_not_identifier() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "Sqlite; fn evil() {}")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Backend = Backend::Postgres;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("not a valid enum variant name"));
+}
+
+#[test]
+fn test_enum_variant_allowed() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "Sqlite")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", variants = [Postgres, Sqlite]
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Backend = Backend::Postgres;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Backend = Backend::Sqlite;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_enum_variant_did_you_mean() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "Sqlit")
+        .build();
+    let attr: TokenStream = quote! {
+        variants = [Postgres, Sqlite]
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Backend = Backend::Postgres;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("did you mean `Sqlite`?"));
+}
+
+#[test]
+fn test_flags_union() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "A|C|D")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Flags = Flags::A.union(Flags::B);
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Flags = Flags::A.union(Flags::C).union(Flags::D);
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_flags_from_single_flag_default() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "A, B")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", flags
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Flags = Flags::A;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Flags = Flags::A.union(Flags::B);
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_flags_empty_from_single_flag_default() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", flags
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Flags = Flags::A;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Flags = Flags::empty();
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_enum_default_rejects_flag_set() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "Postgres,Sqlite")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Backend = Backend::Postgres;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("are not a valid enum variant name"));
+}
+
+#[test]
+fn test_flags_option_requires_flags_default() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "A")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", flags
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: u8 = 1;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("The `flags` option requires the default to be a flag"));
+}
+
+#[test]
+fn test_flags_empty() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Flags = Flags::A.union(Flags::B);
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Flags = Flags::empty();
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_path_default_u32() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1000")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: u32 = u32::MAX;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: u32 = 1000;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_path_default_str() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "custom")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: &str = Self::DEFAULT;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: &str = "custom";
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_path_default_unknown_type() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1")
+        .build();
+    let attr: TokenStream = quote! {
+        ("MYVAR")
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Config = DEFAULT_CONFIG;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("compile_error"));
+}
+
+#[test]
+fn test_choices() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "fast_hash")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", choices = [hashers::fast_hash, hashers::secure_hash]
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: fn(&[u8]) -> u64 = hashers::secure_hash;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: fn(&[u8]) -> u64 = hashers::fast_hash;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_choices_full_path() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "b::hash")
+        .build();
+    let attr: TokenStream = quote! {
+        choices = [a::hash, b::hash]
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: fn(&[u8]) -> u64 = a::hash;
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: fn(&[u8]) -> u64 = b::hash;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_choices_rejects_other_paths() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "std::process::exit")
+        .build();
+    let attr: TokenStream = quote! {
+        choices = [fast_hash, secure_hash]
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: fn(&[u8]) -> u64 = secure_hash;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("not one of the allowed choices"));
+}
+
+#[test]
+fn test_arg() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "250")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", arg
+    };
+    let item: TokenStream = quote! {
+        static MYVAR: AtomicU32 = AtomicU32::new(100);
+    };
+    let expected: TokenStream = quote! {
+        static MYVAR: AtomicU32 = AtomicU32::new(250);
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_arg_index() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "500")
+        .build();
+    let attr: TokenStream = quote! {
+        arg = 1
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Duration = Duration::new(1, 0);
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Duration = Duration::new(1, 500);
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_arg_wrong_kind() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "drop_everything()")
+        .build();
+    let attr: TokenStream = quote! {
+        arg
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Duration = Duration::from_millis(500);
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("not the same kind of literal"));
+}
+
+#[test]
+fn test_arg_out_of_bounds() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1")
+        .build();
+    let attr: TokenStream = quote! {
+        arg = 2
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Duration = Duration::new(1, 0);
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("only has 2 arguments"));
+}
+
+#[test]
+fn test_template_split() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "10.0.0.1")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", template = "Ipv4Addr::new({0}, {1}, {2}, {3})", split = "."
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_template_escapes_strings() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "admin\"); evil(\"")
+        .build();
+    let attr: TokenStream = quote! {
+        template = "User {{ name: {0} }}"
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: User = User { name: "guest" };
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: User = User { name: "admin\"); evil(\"" };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_template_wrong_value_count() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "1,2,3")
+        .build();
+    let attr: TokenStream = quote! {
+        template = "Vec2 {{ x: {0}, y: {1} }}", split = ","
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Vec2 = Vec2 { x: 0, y: 0 };
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("is not used"));
+}
+
+#[test]
+fn test_fields() {
+    let env = TestEnv::builder()
+        .set("MYVAR_X", "1.0")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", fields
+    };
+    let item: TokenStream = quote! {
+        static MYVAR: Vec2 = Vec2 { x: 0., y: 2. };
+    };
+    let expected: TokenStream = quote! {
+        static MYVAR: Vec2 = Vec2 { x: 1.0, y: 2. };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_fields_on_top_of_whole_value() {
+    let env = TestEnv::builder()
+        .set("MYVAR", "Server { host: \"example.com\", port: 80 }")
+        .set("MYVAR_HOST", "localhost")
+        .build();
+    let attr: TokenStream = quote! {
+        fields
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Server = Server { host: "0.0.0.0", port: 8080 };
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Server = Server { host: "localhost", port: 80 };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_fields_requires_struct() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = quote! {
+        fields
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: u32 = 0;
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("requires the default to be a struct expression"));
+}
+
+#[test]
+fn test_fields_path_default() {
+    let env = TestEnv::builder()
+        .set("MYVAR_X", "u8::MAX")
+        .set("MYVAR_Y", "7")
+        .build();
+    let attr: TokenStream = quote! {
+        fields
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Point = Point { x: u8::MIN, y: DEFAULT_Y };
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Point = Point { x: u8::MAX, y: 7 };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_fields_none_default() {
+    let env = TestEnv::builder()
+        .set("MYVAR_PORT", "8080")
+        .set("MYVAR_HOST", "\"localhost\"")
+        .build();
+    let attr: TokenStream = quote! {
+        fields
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Server = Server { host: None, port: None };
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Server = Server { host: Some("localhost"), port: Some(8080) };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_fields_none_default_requires_literal() {
+    let env = TestEnv::builder()
+        .set("MYVAR_HOST", "localhost")
+        .build();
+    let attr: TokenStream = quote! {
+        fields
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Server = Server { host: None };
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Environment variable contents `localhost` are not a literal"));
+}
+
+#[test]
+fn test_fields_enum_default() {
+    let env = TestEnv::builder()
+        .set("MYVAR_BACKEND", "Sqlite")
+        .build();
+    let attr: TokenStream = quote! {
+        fields
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: Db = Db { backend: Backend::Postgres };
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: Db = Db { backend: Backend::Sqlite };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_nested() {
+    let env = TestEnv::builder()
+        .set("APP__DB__PORT", "5433")
+        .set("APP__NAME", "prod")
+        .build();
+    let attr: TokenStream = quote! {
+        nested
+    };
+    let item: TokenStream = quote! {
+        static APP: Config = Config { name: "dev", db: Db { host: "localhost", port: 5432 } };
+    };
+    let expected: TokenStream = quote! {
+        static APP: Config = Config { name: "prod", db: Db { host: "localhost", port: 5433 } };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_nested_on_top_of_subtree() {
+    let env = TestEnv::builder()
+        .set("APP__DB", "Db { host: \"db.internal\", port: 6000 }")
+        .set("APP__DB__PORT", "6001")
+        .build();
+    let attr: TokenStream = quote! {
+        nested
+    };
+    let item: TokenStream = quote! {
+        static APP: Config = Config { db: Db { host: "localhost", port: 5432 } };
+    };
+    let expected: TokenStream = quote! {
+        static APP: Config = Config { db: Db { host: "db.internal", port: 6001 } };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_nested_untyped_leaves() {
+    let env = TestEnv::builder()
+        .set("APP__DB__PORT", "5433")
+        .set("APP__DB__TIMEOUT", "30")
+        .set("APP__DB__POOL", "u16::MAX")
+        .build();
+    let attr: TokenStream = quote! {
+        nested
+    };
+    let item: TokenStream = quote! {
+        static APP: Config = Config { db: Db { port: DEFAULT_PORT, timeout: None, pool: u16::MIN } };
+    };
+    let expected: TokenStream = quote! {
+        static APP: Config = Config { db: Db { port: 5433, timeout: Some(30), pool: u16::MAX } };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_nested_typed_arrays() {
+    let env = TestEnv::builder()
+        .set("GRID__1__0", "5")
+        .build();
+    let attr: TokenStream = quote! {
+        nested
+    };
+    let item: TokenStream = quote! {
+        const GRID: [[Option<u8>; 2]; 2] = [[None, None], [None, None]];
+    };
+    let expected: TokenStream = quote! {
+        const GRID: [[Option<u8>; 2]; 2] = [[None, None], [Some(5), None]];
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_nested_and_fields_conflict() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = quote! {
+        fields, nested
+    };
+    let item: TokenStream = quote! {
+        static APP: Config = Config { db: Db { port: 5432 } };
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("cannot be combined"));
+}
+
+#[test]
+fn test_elements() {
+    let env = TestEnv::builder()
+        .set("MYVAR_1", "25")
+        .build();
+    let attr: TokenStream = quote! {
+        "MYVAR", elements
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: [u16; 3] = [10, 20, 30];
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: [u16; 3] = [10, 25, 30];
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_elements_repeat() {
+    let env = TestEnv::builder()
+        .set("MYVAR_2", "-1")
+        .build();
+    let attr: TokenStream = quote! {
+        elements
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: [i8; 4] = [0; 4];
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: [i8; 4] = [0, 0, -1, 0];
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_elements_typed() {
+    let env = TestEnv::builder()
+        .set("MYVAR_0", "5")
+        .set("MYVAR_1", "u16::MAX")
+        .build();
+    let attr: TokenStream = quote! {
+        elements
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: [Option<u16>; 2] = [None, None];
+    };
+    let expected: TokenStream = quote! {
+        const MYVAR: [Option<u16>; 2] = [Some(5), Some(u16::MAX)];
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_elements_out_of_bounds() {
+    let env = TestEnv::builder()
+        .set("MYVAR_3", "1")
+        .build();
+    let attr: TokenStream = quote! {
+        elements
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: [u16; 3] = [10, 20, 30];
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("out of bounds"));
+}
+
+#[test]
+fn test_elements_far_out_of_bounds() {
+    let env = TestEnv::builder()
+        .set("MYVAR_10", "1")
+        .build();
+    let attr: TokenStream = quote! {
+        elements
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: [u8; 3] = [0; 3];
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Environment variable MYVAR_10 is out of bounds, the default array has 3 elements"));
+}
+
+#[test]
+fn test_nested_out_of_bounds() {
+    let env = TestEnv::builder()
+        .set("BOARD__CHANNELS__4__GAIN", "1.5")
+        .build();
+    let attr: TokenStream = quote! {
+        nested
+    };
+    let item: TokenStream = quote! {
+        static BOARD: Board = Board { channels: [Channel { gain: 1.0 }, Channel { gain: 1.0 }] };
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Environment variable BOARD__CHANNELS__4__GAIN is out of bounds"));
+}
+
+/// Counts the variables read, to check that long repeat defaults aren't read element by element.
+struct CountingEnv {
+    env: TestEnv,
+    reads: Cell<usize>
+}
+
+impl ReadEnv for CountingEnv {
+    fn read_env(&self, var_name: &str) -> Option<String> {
+        self.reads.set(self.reads.get() + 1);
+        self.env.read_env(var_name)
+    }
+
+    fn var_names(&self, prefix: &str) -> Option<Vec<String>> {
+        self.env.var_names(prefix)
+    }
+}
+
+#[test]
+fn test_elements_long_repeat() {
+    let env = CountingEnv {
+        env: TestEnv::builder()
+            .set("MYVAR_4000", "7")
+            .build(),
+        reads: Cell::new(0)
+    };
+    let attr: TokenStream = quote! {
+        elements
+    };
+    let item: TokenStream = quote! {
+        const MYVAR: [u8; 4096] = [0; 4096];
+    };
+    let result = env_item(attr, item, &env);
+    let result = syn::parse2::<syn::ItemConst>(result).unwrap();
+    let syn::Expr::Array(array) = *result.expr else {
+        panic!("expected an array");
+    };
+    assert_eq!(4096, array.elems.len());
+    assert_eq!("7", array.elems[4000].to_token_stream().to_string());
+    assert!(env.reads.get() < 10);
+}
+
+#[test]
+fn test_nested_array() {
+    let env = TestEnv::builder()
+        .set("BOARD__CHANNELS__1__GAIN", "1.5")
+        .build();
+    let attr: TokenStream = quote! {
+        nested
+    };
+    let item: TokenStream = quote! {
+        static BOARD: Board = Board { channels: [Channel { gain: 1.0 }, Channel { gain: 1.0 }] };
+    };
+    let expected: TokenStream = quote! {
+        static BOARD: Board = Board { channels: [Channel { gain: 1.0 }, Channel { gain: 1.5 }] };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_impl_block() {
+    let env = TestEnv::builder()
+        .set("HTTP_SERVER_MAX_CONNECTIONS", "100")
+        .set("TIMEOUT", "60")
+        .build();
+    let attr: TokenStream = TokenStream::new();
+    let item: TokenStream = quote! {
+        impl HttpServer {
+            const MAX_CONNECTIONS: u32 = 10;
+            #[env_item("TIMEOUT")]
+            pub const TIMEOUT_SECS: u64 = 30;
+            const UNCHANGED: bool = false;
+            fn new() -> Self { HttpServer }
+        }
+    };
+    let expected: TokenStream = quote! {
+        impl HttpServer {
+            const MAX_CONNECTIONS: u32 = 100;
+            pub const TIMEOUT_SECS: u64 = 60;
+            const UNCHANGED: bool = false;
+            fn new() -> Self { HttpServer }
+        }
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_trait_block() {
+    let env = TestEnv::builder()
+        .set("APP_LIMITS_BURST", "20")
+        .build();
+    let attr: TokenStream = quote! {
+        prefix = "APP_"
+    };
+    let item: TokenStream = quote! {
+        trait Limits {
+            const BURST: u32 = 5;
+            const RATE: u32;
+        }
+    };
+    let expected: TokenStream = quote! {
+        trait Limits {
+            const BURST: u32 = 20;
+            const RATE: u32;
+        }
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_trait_const_without_default() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = TokenStream::new();
+    let item: TokenStream = quote! {
+        trait Limits {
+            #[env_item]
+            const RATE: u32;
+        }
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("requires a default value"));
+}
+
+#[test]
+fn test_impl_block_name_rejected() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = quote! {
+        "SERVER"
+    };
+    let item: TokenStream = quote! {
+        impl Server {
+            const MAX_CONNECTIONS: u32 = 10;
+        }
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("cannot be given for a whole impl or trait"));
+}
@@ -0,0 +1,158 @@
+use const_env_impl::{env_item, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+#[test]
+fn test_object() {
+    let env = TestEnv::builder()
+        .set("ORIGIN", r#"{"x": 1.5, "y": -2.0}"#)
+        .build();
+    let attr: TokenStream = quote! {
+        format = "json"
+    };
+    let item: TokenStream = quote! {
+        const ORIGIN: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+    };
+    let expected: TokenStream = quote! {
+        const ORIGIN: Vec2 = Vec2 { x: 1.5, y: -2.0 };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_nested() {
+    let env = TestEnv::builder()
+        .set("SERVER", r#"{"name": "api", "ports": [80, 443], "backend": "Sqlite"}"#)
+        .build();
+    let attr: TokenStream = quote! {
+        format = "json"
+    };
+    let item: TokenStream = quote! {
+        const SERVER: Server = Server { name: "localhost", ports: [8080; 2], backend: Backend::Postgres, debug: false };
+    };
+    let expected: TokenStream = quote! {
+        const SERVER: Server = Server { name: "api", ports: [80, 443], backend: Backend::Sqlite, debug: false };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_untyped_fields() {
+    let env = TestEnv::builder()
+        .set("LIMITS", r#"{"max": 5, "level": 3}"#)
+        .build();
+    let attr: TokenStream = quote! {
+        format = "json"
+    };
+    let item: TokenStream = quote! {
+        const LIMITS: Limits = Limits { max: None, level: u8::MIN };
+    };
+    let expected: TokenStream = quote! {
+        const LIMITS: Limits = Limits { max: Some(5), level: 3 };
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_escaped_string() {
+    let env = TestEnv::builder()
+        .set("GREETING", r#""say \"hi\"\né""#)
+        .build();
+    let attr: TokenStream = quote! {
+        format = "json"
+    };
+    let item: TokenStream = quote! {
+        const GREETING: &str = "hello";
+    };
+    let expected: TokenStream = quote! {
+        const GREETING: &str = "say \"hi\"\né";
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_null() {
+    let env = TestEnv::builder()
+        .set("TIMEOUT", "null")
+        .build();
+    let attr: TokenStream = quote! {
+        format = "json"
+    };
+    let item: TokenStream = quote! {
+        const TIMEOUT: Option<u32> = Some(30);
+    };
+    let expected: TokenStream = quote! {
+        const TIMEOUT: Option<u32> = None;
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_invalid_surrogate_pair() {
+    for json in [r#""\uD800\u0041""#, r#""\uD800 \uDC00""#, r#""\u+041""#] {
+        let env = TestEnv::builder()
+            .set("GREETING", json)
+            .build();
+        let attr: TokenStream = quote! {
+            format = "json"
+        };
+        let item: TokenStream = quote! {
+            const GREETING: &str = "hi";
+        };
+        let result = env_item(attr, item, env);
+        assert!(format!("{}", result).contains("Failed to parse environment variable GREETING as JSON"), "{}", json);
+    }
+}
+
+#[test]
+fn test_number_keeps_text() {
+    let env = TestEnv::builder()
+        .set("LIMITS", "[340282366920938463463374607431768211455, 1.50]")
+        .build();
+    let attr: TokenStream = quote! {
+        format = "json"
+    };
+    let item: TokenStream = quote! {
+        const LIMITS: (u128, f64) = (0, 0.0);
+    };
+    let expected: TokenStream = quote! {
+        const LIMITS: (u128, f64) = (340282366920938463463374607431768211455, 1.50);
+    };
+    let result = env_item(attr, item, env);
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_invalid_json() {
+    let env = TestEnv::builder()
+        .set("PORTS", "[80, 443")
+        .build();
+    let attr: TokenStream = quote! {
+        format = "json"
+    };
+    let item: TokenStream = quote! {
+        const PORTS: [u16; 2] = [8080, 8443];
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Failed to parse environment variable PORTS as JSON: EOF while parsing a list at line 1 column 8"));
+}
+
+#[test]
+fn test_unknown_format() {
+    let env = TestEnv::builder()
+        .build();
+    let attr: TokenStream = quote! {
+        format = "yaml"
+    };
+    let item: TokenStream = quote! {
+        const PORTS: [u16; 2] = [8080, 8443];
+    };
+    let result = env_item(attr, item, env);
+    assert!(format!("{}", result).contains("Unknown format `yaml`, expected `json`"));
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use const_env_impl::{env_item, Chain, Fallback, Mapped, Overlay, Prefixed, ReadEnv, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+fn env() -> TestEnv {
+    TestEnv::builder()
+        .set("PORT", "7000")
+        .set("MYAPP_PORT", "9000")
+        .file("/project/build.toml", "port = 1")
+        .build()
+}
+
+fn vars(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn test_chain() {
+    let read_env = Chain(env(), vars(&[("PORT", "1"), ("HOST", "localhost")]));
+    assert_eq!(read_env.read_env("PORT").as_deref(), Some("7000"));
+    assert_eq!(read_env.read_env("HOST").as_deref(), Some("localhost"));
+    assert_eq!(read_env.read_env("MISSING"), None);
+    assert_eq!(read_env.read_file(Path::new("/project/build.toml")).unwrap(), "port = 1");
+}
+
+#[test]
+fn test_chain_reads_file_from_second() {
+    let other = TestEnv::builder()
+        .file("/project/build.toml", "port = 2")
+        .file("/project/.env", "PORT=2")
+        .build();
+    let read_env = Chain(env(), other);
+    assert_eq!(read_env.read_file(Path::new("/project/build.toml")).unwrap(), "port = 1");
+    assert_eq!(read_env.read_file(Path::new("/project/.env")).unwrap(), "PORT=2");
+    assert!(read_env.read_file(Path::new("/project/missing.toml")).is_err());
+}
+
+#[test]
+fn test_fixed_vars_have_no_files() {
+    let read_env = Chain(env(), vars(&[("HOST", "localhost")]));
+    let err = read_env.read_file(Path::new("/etc/hostname")).unwrap_err();
+    assert_eq!(std::io::ErrorKind::NotFound, err.kind());
+}
+
+#[test]
+fn test_prefixed() {
+    let read_env = Prefixed::new("MYAPP_", env());
+    assert_eq!(read_env.read_env("PORT").as_deref(), Some("9000"));
+    assert_eq!(read_env.read_env("MYAPP_PORT"), None);
+}
+
+#[test]
+fn test_mapped() {
+    let read_env = Mapped::new(env(), |name| name.to_uppercase());
+    assert_eq!(read_env.read_env("port").as_deref(), Some("7000"));
+}
+
+#[test]
+fn test_fallback() {
+    let read_env = Fallback::new(env(), |name| Some(format!("default {}", name)));
+    assert_eq!(read_env.read_env("PORT").as_deref(), Some("7000"));
+    assert_eq!(read_env.read_env("HOST").as_deref(), Some("default HOST"));
+}
+
+#[test]
+fn test_overlay() {
+    let read_env = Overlay(vars(&[("PORT", "1")]), env());
+    assert_eq!(read_env.read_env("PORT").as_deref(), Some("1"));
+    assert_eq!(read_env.read_env("MYAPP_PORT").as_deref(), Some("9000"));
+    assert_eq!(read_env.read_file(Path::new("/project/build.toml")).unwrap(), "port = 1");
+}
+
+#[test]
+fn test_var_names() {
+    let read_env = Overlay(vars(&[("HOST", "localhost")]), Prefixed::new("MYAPP_", env()));
+    let mut names = read_env.var_names("").unwrap();
+    names.sort();
+    assert_eq!(vec!["HOST", "PORT"], names);
+    assert_eq!(None, Mapped::new(env(), |name| name.to_uppercase()).var_names("PORT"));
+    assert_eq!(None, Chain(env(), Fallback::new(env(), |_| None)).var_names("PORT"));
+}
+
+#[test]
+fn test_combinators_with_macro() {
+    let read_env: Box<dyn ReadEnv> = Box::new(Overlay(vars(&[("LIMIT", "5")]), Prefixed::new("MYAPP_", env())));
+    let attr: TokenStream = quote! {};
+    let item: TokenStream = quote! {
+        const PORT: u16 = 80;
+    };
+    let expected: TokenStream = quote! {
+        const PORT: u16 = 9000;
+    };
+    let result = env_item(attr, item, &read_env);
+    assert_eq!(expected.to_string(), result.to_string());
+    let attr: TokenStream = quote! {};
+    let item: TokenStream = quote! {
+        const LIMIT: u32 = 0;
+    };
+    let expected: TokenStream = quote! {
+        const LIMIT: u32 = 5;
+    };
+    let result = env_item(attr, item, &read_env);
+    assert_eq!(expected.to_string(), result.to_string());
+}
+
+#[test]
+fn test_dotenv_through_prefixed() {
+    let read_env = Prefixed::new("MYAPP_", TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/.env", "HOST=localhost")
+        .build());
+    let attr: TokenStream = quote! {
+        dotenv
+    };
+    let item: TokenStream = quote! {
+        const HOST: &str = "0.0.0.0";
+    };
+    let expected: TokenStream = quote! {
+        const HOST: &str = "localhost";
+    };
+    let result = env_item(attr, item, read_env);
+    assert_eq!(expected.to_string(), result.to_string());
+}
+
+#[test]
+fn test_dotenv_through_fallback() {
+    let read_env = Fallback::new(TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .file("/project/config.env", "HOST=localhost")
+        .build(), |name| (name != "HOST").then(|| "1".to_string()));
+    let attr: TokenStream = quote! {
+        dotenv = "config.env"
+    };
+    let item: TokenStream = quote! {
+        const HOST: &str = "0.0.0.0";
+    };
+    let expected: TokenStream = quote! {
+        const HOST: &str = "localhost";
+    };
+    let result = env_item(attr, item, &read_env);
+    assert_eq!(expected.to_string(), result.to_string());
+    let attr: TokenStream = quote! {};
+    let item: TokenStream = quote! {
+        const PORT: u16 = 80;
+    };
+    let expected: TokenStream = quote! {
+        const PORT: u16 = 1;
+    };
+    let result = env_item(attr, item, &read_env);
+    assert_eq!(expected.to_string(), result.to_string());
+}
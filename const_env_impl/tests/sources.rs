@@ -0,0 +1,121 @@
+use const_env_impl::{env_item, TestEnv};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+const BUILD_TOML: &str = r#"
+[app]
+port = 9000
+backend = "Sqlte"
+"#;
+
+fn env() -> TestEnv {
+    TestEnv::builder()
+        .set("CARGO_MANIFEST_DIR", "/project")
+        .set("PORT", "7000")
+        .file("/project/local.env", "PORT=8000\nHOST=local.example.com")
+        .file("/project/build.toml", BUILD_TOML)
+        .build()
+}
+
+#[test]
+fn test_first_source_wins() {
+    let attr: TokenStream = quote! {
+        sources = [dotenv("local.env"), env, default]
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 80;
+    };
+    let expected: TokenStream = quote! {
+        const PORT: u16 = 8000;
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_toml_table() {
+    let attr: TokenStream = quote! {
+        sources = [toml("build.toml", "app"), env, default]
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 80;
+    };
+    let expected: TokenStream = quote! {
+        const PORT: u16 = 9000;
+    };
+    let result = env_item(attr, item, env());
+    assert_eq!(format!("{}", expected), format!("{}", result));
+}
+
+#[test]
+fn test_falls_through_to_default() {
+    let attr: TokenStream = quote! {
+        sources = [toml("build.toml", "app"), dotenv("local.env"), default]
+    };
+    let item: TokenStream = quote! {
+        const TIMEOUT: u32 = 30;
+    };
+    let result = env_item(attr, item.clone(), env());
+    assert_eq!(format!("{}", item), format!("{}", result));
+}
+
+#[test]
+fn test_missing_key_falls_through_to_default() {
+    let attr: TokenStream = quote! {
+        key = "app.timeout", sources = [env, toml("build.toml"), default]
+    };
+    let item: TokenStream = quote! {
+        const TIMEOUT: u32 = 30;
+    };
+    let result = env_item(attr, item.clone(), env());
+    assert_eq!(format!("{}", item), format!("{}", result));
+}
+
+#[test]
+fn test_without_default() {
+    let attr: TokenStream = quote! {
+        sources = [env, toml("build.toml", "app")]
+    };
+    let item: TokenStream = quote! {
+        const TIMEOUT: u32 = 30;
+    };
+    let result = env_item(attr, item, env());
+    assert!(format!("{}", result).contains("None of the sources defines TIMEOUT and `default` is not a source"));
+}
+
+#[test]
+fn test_default_not_last() {
+    let attr: TokenStream = quote! {
+        sources = [env, default, toml("build.toml")]
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 80;
+    };
+    let result = env_item(attr, item, env());
+    assert!(format!("{}", result).contains("The `default` source must be the last source"));
+}
+
+#[test]
+fn test_origin_in_diagnostics() {
+    let attr: TokenStream = quote! {
+        sources = [toml("build.toml", "app"), default], variants = [Postgres, Sqlite]
+    };
+    let item: TokenStream = quote! {
+        const BACKEND: Backend = Backend::Postgres;
+    };
+    let result = env_item(attr, item, env());
+    assert!(format!("{}", result).contains("(value of BACKEND from configuration file /project/build.toml)"));
+}
+
+#[test]
+fn test_sources_with_file() {
+    let attr: TokenStream = quote! {
+        sources = [env, default], file = "build.toml"
+    };
+    let item: TokenStream = quote! {
+        const PORT: u16 = 80;
+    };
+    let result = env_item(attr, item, env());
+    assert!(format!("{}", result).contains("The `sources` option cannot be combined with the `file` option"));
+}
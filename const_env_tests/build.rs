@@ -14,6 +14,44 @@ fn main() {
     set_env("SMOKE_STR", "bar");
     set_env("SMOKE_U32", "321");
     set_env("ORIGIN", "Vec2 { x: 1., y: 2.}");
+    set_env("PORTS", "9000-9100");
+    set_env("OFFSETS", "-5..-1");
+    set_env("PROXY", "proxy.local:3128");
+    set_env("RETRIES", "5");
+    set_env("BACKEND", "Sqlite");
+    set_env("FLAGS", "A|C");
+    set_env("MORE_FLAGS", "B,C");
+    set_env("NO_FLAGS", "");
+    set_env("MAX_CONNECTIONS", "1000");
+    set_env("GREETING", "howdy");
+    set_env("HASHER", "fast_hash");
+    set_env("COUNTER_LIMIT", "250");
+    set_env("TIMEOUT_MILLIS", "1500");
+    set_env("BIND_ADDR", "10.0.0.1");
+    set_env("SPAWN", "1.5, -2.0");
+    set_env("CAMERA_X", "3.0");
+    set_env("APP__DB__PORT", "5433");
+    set_env("CALIBRATION_2", "0.98");
+    set_env("SERVER_PORT", "9090");
+    set_env("SERVER_BACKEND", "Sqlite");
+    set_env("BLOCK_BLOCK_PORT", "9091");
+    set_env("BLOCK_HOSTNAME", "example.com");
+    set_env("HTTP_SERVER_MAX_CONNECTIONS", "100");
+    set_env("HTTP_TIMEOUT", "60");
+    set_env("LIMITS_BURST", "20");
+    set_env("FLOAT", "f64");
+    set_env("TELEMETRY", "on");
+    set_env("DEBUG_HOOKS", "");
+    set_env("TYPED_PORT", "9000");
+    set_env("RAW_LIMIT", "1 << 12");
+    set_env("LIMITS", "(-1, true)");
+    set_env("DOTENV_PORT", "9090");
+    set_env("FILE_NAME", "from env");
+    set_env("JSON_ORIGIN", r#"{"x": 1.5, "y": -2.0}"#);
+    set_env("JSON_HOSTS", r#"["a \"quoted\" host", "b.example.com"]"#);
+    set_env("JSON_TIMEOUT", "null");
+    println!("cargo:rerun-if-changed=.env");
+    println!("cargo:rerun-if-changed=build.toml");
 }
 
 fn set_env(name: &str, value: &str) {
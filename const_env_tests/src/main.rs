@@ -1,4 +1,6 @@
-use const_env::{env_item, env_lit};
+#![allow(clippy::redundant_static_lifetimes)]
+
+use const_env::{env_cfg, env_defined, env_item, env_lit, env_match, env_type, EnvConfig};
 
 const USIZE_ARRAY: [usize; 3] = env_lit!("INT_ARRAY", [1, 2, 3]);
 
@@ -68,6 +70,205 @@ static ORIGIN: Vec2<f64> = Vec2 { x: 0., y: 0. };
 
 static ORIGIN_LIT: Vec2<f32> = env_lit!("ORIGIN", Vec2 { x: 0., y: 0.});
 
+#[env_item]
+const PORTS: std::ops::RangeInclusive<u16> = 8000..=8100;
+
+#[env_item]
+const OFFSETS: std::ops::Range<i32> = 0..10;
+
+#[env_item]
+const PROXY: Option<&str> = None;
+
+#[env_item]
+const NO_PROXY: Option<&str> = None;
+
+#[env_item]
+const RETRIES: Option<u8> = Some(3);
+
+#[derive(Eq, PartialEq, Debug)]
+enum Backend {
+    Postgres,
+    Sqlite
+}
+
+#[env_item(variants = [Postgres, Sqlite])]
+const BACKEND: Backend = Backend::Postgres;
+
+#[derive(Eq, PartialEq, Debug)]
+struct Flags(u8);
+
+impl Flags {
+    const A: Flags = Flags(1);
+    const B: Flags = Flags(2);
+    const C: Flags = Flags(4);
+
+    const fn empty() -> Flags {
+        Flags(0)
+    }
+
+    const fn union(self, other: Flags) -> Flags {
+        Flags(self.0 | other.0)
+    }
+}
+
+#[env_item]
+const FLAGS: Flags = Flags::A.union(Flags::B);
+
+#[env_item(flags)]
+const MORE_FLAGS: Flags = Flags::A;
+
+#[env_item]
+const NO_FLAGS: Flags = Flags::A.union(Flags::C);
+
+#[env_item]
+const MAX_CONNECTIONS: u32 = u32::MAX;
+
+struct Server;
+
+impl Server {
+    const DEFAULT_GREETING: &str = "hello";
+
+    #[env_item]
+    const GREETING: &str = Self::DEFAULT_GREETING;
+}
+
+fn fast_hash(bytes: &[u8]) -> u64 {
+    bytes.len() as u64
+}
+
+fn secure_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(17, |hash, byte| hash.wrapping_mul(31).wrapping_add(*byte as u64))
+}
+
+#[env_item(choices = [fast_hash, secure_hash])]
+const HASHER: fn(&[u8]) -> u64 = secure_hash;
+
+#[env_item(arg)]
+static COUNTER_LIMIT: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(100);
+
+#[env_item("TIMEOUT_MILLIS", arg)]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+#[env_item(template = "std::net::Ipv4Addr::new({0}, {1}, {2}, {3})", split = ".")]
+const BIND_ADDR: std::net::Ipv4Addr = std::net::Ipv4Addr::new(127, 0, 0, 1);
+
+#[env_item(template = "Vec2 {{ x: {0}, y: {1} }}", split = ",")]
+const SPAWN: Vec2<f32> = Vec2 { x: 0.0, y: 0.0 };
+
+#[env_item(fields)]
+static CAMERA: Vec2<f64> = Vec2 { x: 0., y: 5. };
+
+#[derive(Eq, PartialEq, Debug)]
+struct Db {
+    host: &'static str,
+    port: u16
+}
+
+#[derive(Eq, PartialEq, Debug)]
+struct Config {
+    name: &'static str,
+    db: Db
+}
+
+#[env_item(nested)]
+static APP: Config = Config { name: "dev", db: Db { host: "localhost", port: 5432 } };
+
+#[env_item(elements)]
+const CALIBRATION: [f32; 4] = [1.0; 4];
+
+#[derive(EnvConfig, Eq, PartialEq, Debug)]
+struct ServerConfig {
+    #[env("SERVER_PORT", default = 8080)]
+    port: u16,
+    #[env("SERVER_HOST", default = "localhost")]
+    host: &'static str,
+    #[env("SERVER_BACKEND", default = Backend::Postgres)]
+    backend: Backend
+}
+
+const SERVER_CONFIG: ServerConfig = ServerConfig::FROM_ENV;
+
+const_env::config! {
+    #![env(prefix = "BLOCK_", listing = BLOCK_ENTRIES)]
+    BLOCK_PORT: u16 = 8080;
+    #[env("BLOCK_HOSTNAME")]
+    BLOCK_HOST: &str = "localhost";
+    BLOCK_VERBOSE: bool = false;
+}
+
+struct HttpServer;
+
+#[env_item]
+impl HttpServer {
+    const MAX_CONNECTIONS: u32 = 10;
+    #[env_item("HTTP_TIMEOUT")]
+    const TIMEOUT_SECS: u64 = 30;
+}
+
+#[env_item]
+trait Limits {
+    const BURST: u32 = 5;
+}
+
+impl Limits for HttpServer {}
+
+#[env_type(choices = [f32, f64])]
+type Float = f32;
+
+#[env_cfg("TELEMETRY" = "on")]
+const TELEMETRY: &str = "enabled";
+
+#[env_cfg(not("TELEMETRY" = "on"))]
+const TELEMETRY: &str = "disabled";
+
+#[env_cfg(any(defined("DEBUG_HOOKS"), defined("NOT_DEFINED")))]
+fn debug_hooks() -> &'static str {
+    "installed"
+}
+
+const DEFAULT_GREETING: &str = "hi";
+
+const TYPED_STR: &str = env_lit!("QUOTED_STR" as &str, DEFAULT_GREETING);
+
+const TYPED_PORT: u16 = env_lit!(u16, "TYPED_PORT", 8080);
+
+#[env_item(dotenv)]
+const DOTENV_GREETING: &str = "hi";
+
+#[env_item(dotenv = ".env")]
+const DOTENV_PORT: u16 = 80;
+
+#[env_item(file = "build.toml", key = "server.port")]
+const FILE_PORT: u16 = 8080;
+
+#[env_item(file = "build.toml", key = "server.origin")]
+static FILE_ORIGIN: Vec2<f32> = Vec2 { x: 0., y: 0. };
+
+#[env_item(file = "build.toml", key = "server.workers")]
+const FILE_WORKERS: [u8; 3] = [0; 3];
+
+#[env_item(file = "build.toml", key = "server.name")]
+const FILE_NAME: &str = "default";
+
+#[env_item(format = "json")]
+static JSON_ORIGIN: Vec2<f32> = Vec2 { x: 0., y: 0. };
+
+#[env_item(format = "json")]
+const JSON_HOSTS: &[&str] = &["localhost"];
+
+#[env_item(format = "json")]
+const JSON_TIMEOUT: Option<u32> = Some(30);
+
+#[env_item("DOTENV_PORT", sources = [dotenv, env, default])]
+const LAYERED_PORT: u16 = 80;
+
+#[env_item(sources = [env, toml("build.toml", "server"), default])]
+const WORKERS: [u8; 3] = [0; 3];
+
+const RAW_LIMIT: usize = env_lit!("RAW_LIMIT", 1 << 10, raw);
+
+const LIMITS: (i32, bool) = env_lit!("LIMITS", (0, false));
+
 fn main() {
     assert_eq!([10, 11, 12], USIZE_ARRAY);
     assert_eq!(["bar"], STRING_ARRAY);
@@ -94,6 +295,64 @@ fn main() {
     assert_eq!(321, SMOKE_U32_LIT);
     assert_eq!(Vec2 { x: 1., y: 2.}, ORIGIN);
     assert_eq!(Vec2 { x: 1., y: 2.}, ORIGIN_LIT);
+    assert_eq!(9000..=9100, PORTS);
+    assert_eq!(-5..-1, OFFSETS);
+    assert_eq!(Some("proxy.local:3128"), PROXY);
+    assert_eq!(None, NO_PROXY);
+    assert_eq!(Some(5), RETRIES);
+    assert_eq!(Backend::Sqlite, BACKEND);
+    assert_ne!(Backend::Postgres, BACKEND);
+    assert_eq!(Flags(5), FLAGS);
+    assert_eq!(Flags(6), MORE_FLAGS);
+    assert_eq!(Flags(0), NO_FLAGS);
+    assert_eq!(1000, MAX_CONNECTIONS);
+    assert_eq!("howdy", Server::GREETING);
+    assert_eq!("hello", Server::DEFAULT_GREETING);
+    assert_eq!(3, HASHER(b"abc"));
+    assert_ne!(3, secure_hash(b"abc"));
+    assert_eq!(250, COUNTER_LIMIT.load(std::sync::atomic::Ordering::Relaxed));
+    assert_eq!(std::time::Duration::from_millis(1500), TIMEOUT);
+    assert_eq!(std::net::Ipv4Addr::new(10, 0, 0, 1), BIND_ADDR);
+    assert_eq!(Vec2 { x: 1.5, y: -2.0 }, SPAWN);
+    assert_eq!(Vec2 { x: 3.0, y: 5.0 }, CAMERA);
+    assert_eq!(Config { name: "dev", db: Db { host: "localhost", port: 5433 } }, APP);
+    assert_eq!([1.0, 1.0, 0.98, 1.0], CALIBRATION);
+    assert_eq!(ServerConfig { port: 9090, host: "localhost", backend: Backend::Sqlite }, SERVER_CONFIG);
+    assert_eq!(9091, BLOCK_PORT);
+    assert_eq!("example.com", BLOCK_HOST);
+    assert_eq!(("BLOCK_VERBOSE", "BLOCK_BLOCK_VERBOSE", "false"), BLOCK_ENTRIES[2]);
+    assert_eq!(("BLOCK_PORT", "BLOCK_BLOCK_PORT", "9091"), BLOCK_ENTRIES[0]);
+    assert_eq!(3, BLOCK_ENTRIES.len());
+    assert_eq!(100, HttpServer::MAX_CONNECTIONS);
+    assert_eq!(60, HttpServer::TIMEOUT_SECS);
+    assert_eq!(20, HttpServer::BURST);
+    assert_eq!(8, std::mem::size_of::<Float>());
+    assert_eq!("enabled", TELEMETRY);
+    assert_eq!("installed", debug_hooks());
+    let driver = env_match!("BACKEND", {
+        "Postgres" => "pg",
+        "Sqlite" | "sqlite" => "sqlite",
+        _ => "none"
+    });
+    assert_eq!("sqlite", driver);
+    let hooks = [env_defined!("DEBUG_HOOKS"), env_defined!("NOT_DEFINED")];
+    assert_eq!([true, false], hooks);
+    assert_eq!("hello world", TYPED_STR);
+    assert_ne!(DEFAULT_GREETING, TYPED_STR);
+    assert_eq!(9000, TYPED_PORT);
+    assert_eq!(4096, RAW_LIMIT);
+    assert_eq!("hello from .env", DOTENV_GREETING);
+    assert_eq!(9090, DOTENV_PORT);
+    assert_eq!(9000, FILE_PORT);
+    assert_eq!(Vec2 { x: 1.5, y: -2.0 }, FILE_ORIGIN);
+    assert_eq!([1, 2, 4], FILE_WORKERS);
+    assert_eq!("from env", FILE_NAME);
+    assert_eq!(Vec2 { x: 1.5, y: -2.0 }, JSON_ORIGIN);
+    assert_eq!(["a \"quoted\" host", "b.example.com"], JSON_HOSTS);
+    assert_eq!(None, JSON_TIMEOUT);
+    assert_eq!(7070, LAYERED_PORT);
+    assert_eq!([1, 2, 4], WORKERS);
+    assert_eq!((-1, true), LIMITS);
 
     println!("Tests succeeded!");
 }
\ No newline at end of file